@@ -0,0 +1,223 @@
+//! Declarative fixtures seeded into the local network.
+//!
+//! Accounts are referred to by their index in the anvil accounts, and every transaction is sent
+//! by the account it concerns, so seeding does not go through the gateway.
+//!
+//! ```json
+//! {
+//!   "dids": [
+//!     { "account": 1, "installations": ["0x0102"] },
+//!     { "account": 2, "deactivated": true }
+//!   ],
+//!   "conversations": [
+//!     { "id": "0x00…00", "messages": [{ "account": 1, "payload": "0x68656c6c6f" }] }
+//!   ]
+//! }
+//! ```
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Error};
+use ethers::{
+    signers::LocalWallet,
+    types::{Address, Bytes, H256, U256},
+    utils::AnvilInstance,
+};
+use lib_didethresolver::{
+    did_registry::DIDRegistry,
+    types::{KeyEncoding, XmtpAttribute, XmtpKeyPurpose},
+};
+use lib_xps::{config::Contracts, rpc::DEFAULT_ATTRIBUTE_VALIDITY};
+use messaging::Conversation;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Fixtures {
+    #[serde(default)]
+    pub dids: Vec<DidFixture>,
+    #[serde(default)]
+    pub conversations: Vec<ConversationFixture>,
+}
+
+/// The DID of an anvil account
+#[derive(Debug, Deserialize)]
+pub struct DidFixture {
+    pub account: usize,
+    /// Key packages granted as installations
+    #[serde(default)]
+    pub installations: Vec<Bytes>,
+    /// Deactivate the DID once its installations are granted
+    #[serde(default)]
+    pub deactivated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationFixture {
+    pub id: H256,
+    #[serde(default)]
+    pub messages: Vec<MessageFixture>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageFixture {
+    pub account: usize,
+    pub payload: Bytes,
+}
+
+impl Fixtures {
+    /// Reads fixtures from a JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Sends the transactions creating these fixtures on `anvil`
+    pub async fn seed(&self, anvil: &AnvilInstance, contracts: &Contracts) -> Result<(), Error> {
+        for fixture in &self.dids {
+            let client = crate::client(anvil, account(anvil, fixture.account)?).await;
+            let identity = client.address();
+            let registry = DIDRegistry::new(contracts.registry, client);
+
+            let name: [u8; 32] = XmtpAttribute {
+                purpose: XmtpKeyPurpose::Installation,
+                encoding: KeyEncoding::Hex,
+            }
+            .into();
+            for key_package in &fixture.installations {
+                // installations are stored hex encoded
+                let value = hex::encode(key_package).into_bytes();
+                registry
+                    .set_attribute(
+                        identity,
+                        name,
+                        value.into(),
+                        U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+                    )
+                    .send()
+                    .await?
+                    .await?;
+            }
+
+            if fixture.deactivated {
+                registry
+                    .change_owner(identity, Address::zero())
+                    .send()
+                    .await?
+                    .await?;
+            }
+            println!(
+                "Seeded DID 0x{} with {} installation(s){}",
+                hex::encode(identity),
+                fixture.installations.len(),
+                if fixture.deactivated {
+                    ", deactivated"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        for fixture in &self.conversations {
+            for message in &fixture.messages {
+                let client = crate::client(anvil, account(anvil, message.account)?).await;
+                Conversation::new(contracts.conversation, client)
+                    .send_message(fixture.id.into(), message.payload.clone())
+                    .send()
+                    .await?
+                    .await?;
+            }
+            println!(
+                "Seeded conversation {:#x} with {} message(s)",
+                fixture.id,
+                fixture.messages.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn account(anvil: &AnvilInstance, index: usize) -> Result<LocalWallet, Error> {
+    anvil
+        .keys()
+        .get(index)
+        .map(|key| key.clone().into())
+        .ok_or_else(|| anyhow!("fixture refers to account {index}, which anvil does not have"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::{Provider, Ws};
+    use registry::{error::ContactOperationError, ContactOperations};
+
+    /// The example of the module documentation
+    fn example() -> Result<Fixtures, Error> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "dids": [
+                { "account": 1, "installations": ["0x0102"] },
+                { "account": 2, "deactivated": true }
+            ],
+            "conversations": [
+                {
+                    "id": format!("{:#x}", H256::zero()),
+                    "messages": [{ "account": 1, "payload": "0x68656c6c6f" }]
+                }
+            ]
+        }))?)
+    }
+
+    #[test]
+    fn test_parse_fixtures() -> Result<(), Error> {
+        let fixtures = example()?;
+
+        assert_eq!(fixtures.dids.len(), 2);
+        assert_eq!(
+            fixtures.dids[0].installations,
+            vec![Bytes::from(vec![1, 2])]
+        );
+        assert!(!fixtures.dids[0].deactivated);
+        assert!(fixtures.dids[1].installations.is_empty());
+        assert!(fixtures.dids[1].deactivated);
+        assert_eq!(
+            fixtures.conversations[0].messages[0].payload,
+            Bytes::from(b"hello".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed() -> Result<(), Error> {
+        let anvil = crate::anvil().spawn();
+        let contracts = crate::deploy_to_anvil(&anvil).await?;
+        example()?.seed(&anvil, &contracts).await?;
+
+        let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+        let operations = ContactOperations::new(DIDRegistry::new(contracts.registry, provider));
+        let did = |index: usize| format!("0x{}", hex::encode(anvil.addresses()[index]));
+
+        let result = operations.fetch_key_packages(did(1)).await?;
+        assert_eq!(result.installation, vec![vec![1, 2]]);
+
+        let result = operations.fetch_key_packages(did(2)).await;
+        assert!(matches!(result, Err(ContactOperationError::DIDDeactivated)));
+
+        // accounts without fixtures are left alone
+        let result = operations.fetch_key_packages(did(3)).await?;
+        assert!(result.installation.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_unknown_account() -> Result<(), Error> {
+        let anvil = crate::anvil().spawn();
+        let contracts = crate::deploy_to_anvil(&anvil).await?;
+        let fixtures: Fixtures = serde_json::from_value(serde_json::json!({
+            "dids": [{ "account": anvil.keys().len() }]
+        }))?;
+
+        let err = fixtures.seed(&anvil, &contracts).await.unwrap_err();
+        assert!(err.to_string().contains("which anvil does not have"));
+        Ok(())
+    }
+}
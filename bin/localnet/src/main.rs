@@ -1,50 +1,170 @@
+mod fixtures;
+mod proxy;
+mod snapshot;
+
 use anyhow::Error;
+use clap::Parser;
 use ethers::{
     abi::Address,
     core::utils::Anvil,
     middleware::SignerMiddleware,
-    providers::{Provider, Ws},
+    providers::{Middleware, Provider, Ws},
     signers::{LocalWallet, Signer as _},
-    utils::AnvilInstance,
+    types::TransactionRequest,
+    utils::{parse_ether, AnvilInstance},
 };
+use fixtures::Fixtures;
 use lib_didethresolver::did_registry::DIDRegistry;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use lib_xps::{
+    config::{Account, Contracts, NetworkConfig},
+    RunningGateway,
+};
+use messaging::Conversation;
+use snapshot::Snapshot;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "localnet",
+    about = "Local anvil network with the XPS contracts deployed"
+)]
+struct Args {
+    /// Where to write the network configuration, which `xps --config` consumes
+    #[arg(short = 'o', long = "output", default_value = "localnet.json")]
+    output: PathBuf,
+    /// Also run a gateway against the network, with a funded wallet
+    #[arg(long = "with-gateway")]
+    with_gateway: bool,
+    /// Port of the gateway started with `--with-gateway`
+    #[arg(long = "gateway-port", default_value_t = 0)]
+    gateway_port: u16,
+    /// JSON file of fixtures to seed, see the `fixtures` module for the format
+    #[arg(long = "fixtures")]
+    fixtures: Option<PathBuf>,
+    /// Restore a snapshot written with `--dump-state`, instead of deploying the contracts
+    #[arg(long = "load-state")]
+    load_state: Option<PathBuf>,
+    /// Write a snapshot of the chain state on shutdown
+    #[arg(long = "dump-state")]
+    dump_state: Option<PathBuf>,
+    /// File created once the network is ready, holding the path of the configuration
+    #[arg(long = "ready-file")]
+    ready_file: Option<PathBuf>,
+    /// Shut down after this many seconds, instead of waiting for a signal
+    #[arg(long = "timeout")]
+    timeout: Option<u64>,
+}
+
+/// Printed on its own line once the network is ready, for scripts waiting on stdout
+const READY_MARKER: &str = "LOCALNET_READY";
+
+/// Ether sent to the wallet of a gateway started with `--with-gateway`
+const GATEWAY_FUNDING_ETHER: u64 = 100;
+
+type Client = Arc<SignerMiddleware<Provider<Ws>, LocalWallet>>;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
+    let args = Args::parse();
+    // listened to before deploying, so a signal received meanwhile still shuts down cleanly
+    let signal = tokio::spawn(lib_xps::shutdown::signal());
 
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
+    let (anvil, mut config) = deploy(&args).await?;
+    let gateway = if args.with_gateway {
+        let gateway = start_gateway(&anvil, &config, args.gateway_port).await?;
+        let url = format!("ws://{}", gateway.addr);
+        println!("Gateway RPC: {}", url);
+        config.gateway = Some(url);
+        Some(gateway)
+    } else {
+        None
+    };
+    config.save(&args.output)?;
+    println!("Configuration written to {}", args.output.display());
 
-    let anvil = deploy().await?;
+    if let Some(path) = &args.ready_file {
+        write_atomically(path, &args.output.display().to_string())?;
+    }
+    println!("{}", READY_MARKER);
+
+    let timeout = async {
+        match args.timeout {
+            Some(seconds) => tokio::time::sleep(Duration::from_secs(seconds)).await,
+            None => std::future::pending().await,
+        }
+    };
+    println!("Waiting for Ctrl-C or SIGTERM...");
+    tokio::select! {
+        signal = signal => println!("Received {}", signal??),
+        _ = timeout => println!("Timed out after {} seconds", args.timeout.unwrap_or_default()),
+    }
 
-    println!("Waiting for Ctrl-C...");
-    while running.load(Ordering::SeqCst) {}
+    if let Some(gateway) = gateway {
+        gateway.shutdown().await?;
+    }
+    if let Some(path) = &args.dump_state {
+        let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+        Snapshot::dump(&provider, config.contracts.clone())
+            .await?
+            .save(path)?;
+        println!("Chain state written to {}", path.display());
+    }
+    if let Some(path) = &args.ready_file {
+        fs::remove_file(path)?;
+    }
     drop(anvil);
     println!("Shutting down...");
     Ok(())
 }
 
-async fn deploy() -> Result<AnvilInstance, Error> {
-    let anvil = Anvil::new()
-        .port(8545_u16)
-        .args(vec![
-            "--base-fee",
-            "35",
-            "--gas-price",
-            "50",
-            "--disable-block-gas-limit",
-        ])
-        .spawn();
-    let registry_address = deploy_to_anvil(&anvil).await?;
+/// Writes `contents` to `path` through a temporary file, so readers never see a partial file
+fn write_atomically(path: &Path, contents: &str) -> Result<(), Error> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Anvil configured as the local network, on a random port unless one is set
+fn anvil() -> Anvil {
+    Anvil::new().args(vec![
+        "--base-fee",
+        "35",
+        "--gas-price",
+        "50",
+        "--disable-block-gas-limit",
+    ])
+}
+
+async fn deploy(args: &Args) -> Result<(AnvilInstance, NetworkConfig), Error> {
+    let anvil = anvil().port(8545_u16).spawn();
+    let contracts = match &args.load_state {
+        Some(path) => {
+            let snapshot = Snapshot::load(path)?;
+            let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+            snapshot.restore(&provider).await?;
+            println!("Chain state restored from {}", path.display());
+            snapshot.contracts
+        }
+        None => deploy_to_anvil(&anvil).await?,
+    };
+    if let Some(path) = &args.fixtures {
+        Fixtures::load(path)?.seed(&anvil, &contracts).await?;
+    }
     println!(
         "Registry deployed at {}, at endpoint {}",
-        hex::encode(registry_address),
+        hex::encode(contracts.registry),
+        anvil.ws_endpoint()
+    );
+    println!(
+        "Conversation deployed at {}, at endpoint {}",
+        hex::encode(contracts.conversation),
         anvil.ws_endpoint()
     );
 
@@ -63,10 +183,28 @@ async fn deploy() -> Result<AnvilInstance, Error> {
         println!("0x{}", hex::encode(address));
     }
 
-    Ok(anvil)
+    let accounts = anvil
+        .keys()
+        .iter()
+        .zip(anvil.addresses())
+        .map(|(key, address)| Account {
+            address: *address,
+            private_key: format!("0x{}", hex::encode(key.to_bytes())),
+        })
+        .collect();
+    let config = NetworkConfig {
+        chain_id: anvil.chain_id(),
+        endpoint: anvil.endpoint(),
+        ws_endpoint: anvil.ws_endpoint(),
+        contracts,
+        accounts,
+        gateway: None,
+    };
+
+    Ok((anvil, config))
 }
 
-async fn deploy_to_anvil(anvil: &AnvilInstance) -> Result<Address, Error> {
+async fn deploy_to_anvil(anvil: &AnvilInstance) -> Result<Contracts, Error> {
     println!("Deploying Registry to local anvil");
 
     let wallet: LocalWallet = anvil.keys()[0].clone().into();
@@ -79,17 +217,76 @@ async fn deploy_to_anvil(anvil: &AnvilInstance) -> Result<Address, Error> {
         .await
         .unwrap();
 
-    Ok(registry.address())
+    println!("Deploying Conversation to local anvil");
+    let conversation = deploy_conversation(&client).await?;
+
+    Ok(Contracts {
+        registry: registry.address(),
+        conversation,
+    })
+}
+
+/// Deploys `Conversation` behind a proxy and initializes it, with the deployer as role admin
+async fn deploy_conversation(client: &Client) -> Result<Address, Error> {
+    let implementation = Conversation::deploy(client.clone(), ())?
+        .gas_price(100)
+        .send()
+        .await?;
+
+    let tx = TransactionRequest::new()
+        .data(proxy::creation_code(implementation.address()))
+        .gas_price(100);
+    let proxy = client
+        .send_transaction(tx, None)
+        .await?
+        .await?
+        .and_then(|receipt| receipt.contract_address)
+        .ok_or_else(|| anyhow::anyhow!("Conversation proxy was not deployed"))?;
+
+    Conversation::new(proxy, client.clone())
+        .initialize(client.address())
+        .gas_price(100)
+        .send()
+        .await?
+        .await?;
+
+    Ok(proxy)
 }
 
-async fn client(
+/// Runs a gateway in-process, funding its wallet from the first anvil account
+async fn start_gateway(
     anvil: &AnvilInstance,
-    wallet: LocalWallet,
-) -> Arc<SignerMiddleware<Provider<Ws>, LocalWallet>> {
+    config: &NetworkConfig,
+    port: u16,
+) -> Result<RunningGateway<Provider<Ws>>, Error> {
+    let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+    let gateway = lib_xps::start(
+        "127.0.0.1".to_string(),
+        port,
+        provider,
+        config.contracts.clone(),
+        lib_xps::ServerOptions::default(),
+    )
+    .await?;
+
+    let wallet: LocalWallet = anvil.keys()[0].clone().into();
+    let client = client(anvil, wallet).await;
+    let tx = TransactionRequest::pay(gateway.signer, parse_ether(GATEWAY_FUNDING_ETHER)?);
+    client.send_transaction(tx, None).await?.await?;
+    println!(
+        "Gateway wallet 0x{} funded with {} ETH",
+        hex::encode(gateway.signer),
+        GATEWAY_FUNDING_ETHER
+    );
+
+    Ok(gateway)
+}
+
+async fn client(anvil: &AnvilInstance, wallet: LocalWallet) -> Client {
     let provider = Provider::<Ws>::connect(anvil.ws_endpoint())
         .await
         .unwrap()
-        .interval(std::time::Duration::from_millis(10u64));
+        .interval(Duration::from_millis(10u64));
     Arc::new(SignerMiddleware::new(
         provider,
         wallet.with_chain_id(anvil.chain_id()),
@@ -0,0 +1,94 @@
+//! Minimal ERC1967 proxy, used to deploy upgradeable contracts such as `Conversation`.
+//!
+//! Upgradeable contracts disable their initializers in the constructor, so they can only be
+//! initialized through a proxy. The proxy stores the implementation in the ERC1967
+//! implementation slot and delegates every call to it, which is all `UUPSUpgradeable` expects.
+
+use ethers::types::{Address, Bytes};
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+const IMPLEMENTATION_SLOT: [u8; 32] = [
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+];
+
+/// Length of the creation code preceding the runtime code
+const CONSTRUCTOR_LENGTH: u8 = 0x43;
+/// Length of the runtime code
+const RUNTIME_LENGTH: u8 = 0x3d;
+
+/// Creation code of a proxy delegating to `implementation`
+pub fn creation_code(implementation: Address) -> Bytes {
+    let mut code = Vec::with_capacity(usize::from(CONSTRUCTOR_LENGTH + RUNTIME_LENGTH));
+
+    // sstore(IMPLEMENTATION_SLOT, implementation)
+    code.push(0x73); // PUSH20
+    code.extend_from_slice(implementation.as_bytes());
+    code.push(0x7f); // PUSH32
+    code.extend_from_slice(&IMPLEMENTATION_SLOT);
+    code.push(0x55); // SSTORE
+
+    // codecopy(0, CONSTRUCTOR_LENGTH, RUNTIME_LENGTH); return(0, RUNTIME_LENGTH)
+    code.extend_from_slice(&[
+        0x60,
+        RUNTIME_LENGTH,
+        0x60,
+        CONSTRUCTOR_LENGTH,
+        0x60,
+        0x00,
+        0x39,
+    ]);
+    code.extend_from_slice(&[0x60, RUNTIME_LENGTH, 0x60, 0x00, 0xf3]);
+    debug_assert_eq!(code.len(), usize::from(CONSTRUCTOR_LENGTH));
+
+    code.extend_from_slice(&runtime_code());
+    code.into()
+}
+
+/// Copies the calldata, delegates to the implementation and bubbles up the result
+fn runtime_code() -> Vec<u8> {
+    let mut code = Vec::with_capacity(usize::from(RUNTIME_LENGTH));
+
+    // calldatacopy(0, 0, calldatasize())
+    code.extend_from_slice(&[0x36, 0x3d, 0x3d, 0x37]);
+    // delegatecall(gas(), sload(IMPLEMENTATION_SLOT), 0, calldatasize(), 0, 0)
+    code.extend_from_slice(&[0x3d, 0x3d, 0x36, 0x3d]);
+    code.push(0x7f); // PUSH32
+    code.extend_from_slice(&IMPLEMENTATION_SLOT);
+    code.extend_from_slice(&[0x54, 0x5a, 0xf4]);
+    // returndatacopy(0, 0, returndatasize())
+    code.extend_from_slice(&[0x3d, 0x60, 0x00, 0x80, 0x3e]);
+    // jumpi(success, ok); revert(0, returndatasize())
+    code.extend_from_slice(&[0x60, 0x38, 0x57, 0x3d, 0x60, 0x00, 0xfd]);
+    // ok: return(0, returndatasize())
+    code.extend_from_slice(&[0x5b, 0x3d, 0x60, 0x00, 0xf3]);
+    debug_assert_eq!(code.len(), usize::from(RUNTIME_LENGTH));
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::keccak256;
+
+    #[test]
+    fn test_implementation_slot() {
+        let mut slot = keccak256("eip1967.proxy.implementation");
+        slot[31] -= 1;
+        assert_eq!(slot, IMPLEMENTATION_SLOT);
+    }
+
+    #[test]
+    fn test_creation_code() {
+        let implementation = Address::from_low_u64_be(0xabcd);
+        let code = creation_code(implementation);
+        assert_eq!(
+            code.len(),
+            usize::from(CONSTRUCTOR_LENGTH) + usize::from(RUNTIME_LENGTH)
+        );
+        assert_eq!(&code[1..21], implementation.as_bytes());
+        // the jump destination in the runtime code
+        assert_eq!(code[usize::from(CONSTRUCTOR_LENGTH) + 0x38], 0x5b);
+    }
+}
@@ -0,0 +1,88 @@
+//! Snapshots of the anvil chain state, to share a known state of the network.
+//!
+//! A snapshot holds the state returned by `anvil_dumpState` alongside the addresses of the
+//! contracts, since a restored network is not deployed again.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Error};
+use ethers::{
+    providers::{Provider, Ws},
+    types::Bytes,
+};
+use lib_xps::config::Contracts;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub contracts: Contracts,
+    pub state: Bytes,
+}
+
+impl Snapshot {
+    /// Dumps the state of the node behind `provider`
+    pub async fn dump(provider: &Provider<Ws>, contracts: Contracts) -> Result<Self, Error> {
+        let state = provider.request("anvil_dumpState", ()).await?;
+        Ok(Self { contracts, state })
+    }
+
+    /// Restores this snapshot into the node behind `provider`
+    pub async fn restore(&self, provider: &Provider<Ws>) -> Result<(), Error> {
+        let loaded: bool = provider
+            .request("anvil_loadState", [self.state.clone()])
+            .await?;
+        if !loaded {
+            return Err(anyhow!("anvil did not load the snapshot"));
+        }
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Middleware;
+    use lib_didethresolver::did_registry::DIDRegistry;
+
+    #[tokio::test]
+    async fn test_dump_restore() -> Result<(), Error> {
+        let anvil = crate::anvil().spawn();
+        let contracts = crate::deploy_to_anvil(&anvil).await?;
+        let owner = anvil.addresses()[1];
+        let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+        let path = std::env::temp_dir().join(format!("localnet-{}.json", std::process::id()));
+        Snapshot::dump(&provider, contracts.clone())
+            .await?
+            .save(&path)?;
+        drop(anvil);
+
+        let anvil = crate::anvil().spawn();
+        let provider = Provider::<Ws>::connect(anvil.ws_endpoint()).await?;
+        assert!(provider
+            .get_code(contracts.registry, None)
+            .await?
+            .is_empty());
+
+        let snapshot = Snapshot::load(&path)?;
+        fs::remove_file(&path)?;
+        snapshot.restore(&provider).await?;
+        assert_eq!(snapshot.contracts, contracts);
+
+        for address in [contracts.registry, contracts.conversation] {
+            assert!(!provider.get_code(address, None).await?.is_empty());
+        }
+        let registry = DIDRegistry::new(contracts.registry, provider);
+        assert_eq!(registry.identity_owner(owner).call().await?, owner);
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use ethers::{
+    signers::LocalWallet,
+    types::{Address, Bytes, H256},
+};
+use jsonrpsee::ws_client::WsClient;
+use serde::Serialize;
+use std::{path::PathBuf, str::FromStr};
+use xps_client::Gateway;
+use xps_types::{rpc::XpsClient, Status};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "xps-cli",
+    version = "0.1.0",
+    about = "Command line client for the XMTP Postal Service"
+)]
+struct Args {
+    /// Websocket endpoint of the gateway, e.g. the `Gateway RPC` printed by
+    /// `localnet --with-gateway`. `xps` listens on a random port unless given `--port`.
+    #[arg(short = 'e', long = "endpoint")]
+    endpoint: String,
+    /// File containing the hex encoded private key used to sign requests
+    #[arg(short = 'k', long = "key-file")]
+    key_file: Option<PathBuf>,
+    /// Print results as JSON
+    #[arg(long = "json")]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Status of the gateway
+    Status,
+    /// Balance of the gateway wallet
+    Balance,
+    /// Address of the gateway wallet
+    WalletAddress,
+    /// Registry nonce of a DID
+    Nonce { did: String },
+    /// Key packages of every installation registered for a DID
+    FetchKeyPackages { did: Address },
+    /// Grant an installation to the DID owned by the key file
+    Grant {
+        /// Hex encoded key package
+        key_package: String,
+    },
+    /// Revoke an installation from the DID owned by the key file
+    Revoke {
+        /// Hex encoded key package
+        key_package: String,
+    },
+    /// Send a message signed by the key file to a conversation
+    Send {
+        /// Hex encoded 32-byte conversation ID
+        #[arg(short = 'c', long = "conversation")]
+        conversation: String,
+        /// Hex encoded payload
+        payload: Bytes,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let gateway = Gateway::connect(&args.endpoint)
+        .await
+        .with_context(|| format!("connecting to {}", args.endpoint))?;
+    println!("{}", execute(&gateway, &args).await?);
+    Ok(())
+}
+
+/// Runs the command, returning what to print
+async fn execute(gateway: &Gateway<WsClient>, args: &Args) -> Result<String> {
+    let client = gateway.client();
+    match &args.command {
+        Command::Status => {
+            let status = client.status().await?;
+            render(args.json, &status, || status.clone())
+        }
+        Command::Balance => {
+            let balance = client.balance().await?;
+            render(args.json, &balance, || balance.to_string())
+        }
+        Command::WalletAddress => {
+            let address = client.wallet_address().await?;
+            render(args.json, &address, || format!("{:#x}", address))
+        }
+        Command::Nonce { did } => {
+            let nonce = client.nonce(did.clone()).await?;
+            render(args.json, &nonce, || nonce.to_string())
+        }
+        Command::FetchKeyPackages { did } => {
+            let installations = gateway
+                .installations(*did)
+                .await?
+                .into_iter()
+                .map(|key_package| format!("0x{}", hex::encode(key_package)))
+                .collect::<Vec<_>>();
+            render(args.json, &installations, || installations.join("\n"))
+        }
+        Command::Grant { key_package } => {
+            let wallet = wallet(args)?;
+            let result = gateway
+                .register_installation(&wallet, &decode_hex(key_package)?)
+                .await?;
+            render(args.json, &result, || {
+                outcome(&result.message, &result.status, result.transaction)
+            })
+        }
+        Command::Revoke { key_package } => {
+            let wallet = wallet(args)?;
+            let result = gateway
+                .revoke_installation(&wallet, &decode_hex(key_package)?)
+                .await?;
+            render(args.json, &result, || {
+                outcome(&result.message, &result.status, result.transaction)
+            })
+        }
+        Command::Send {
+            conversation,
+            payload,
+        } => {
+            let wallet = wallet(args)?;
+            let conversation_id: [u8; 32] = decode_hex(conversation)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("conversation ID must be 32 bytes"))?;
+            let result = gateway
+                .send(&wallet, conversation_id, payload.clone())
+                .await?;
+            render(args.json, &result, || {
+                format!("{} (transaction {})", result.message, result.transaction)
+            })
+        }
+    }
+}
+
+/// Renders `value` as JSON, or as the human-readable text built by `human`
+fn render<T: Serialize>(json: bool, value: &T, human: impl FnOnce() -> String) -> Result<String> {
+    if json {
+        Ok(serde_json::to_string_pretty(value)?)
+    } else {
+        Ok(human())
+    }
+}
+
+/// Human-readable outcome of an operation changing state
+fn outcome(message: &str, status: &Status, transaction: Option<H256>) -> String {
+    match transaction {
+        Some(transaction) => format!("{message} ({status}, transaction {transaction:#x})"),
+        None => format!("{message} ({status})"),
+    }
+}
+
+/// Loads the wallet from the key file passed on the command line
+fn wallet(args: &Args) -> Result<LocalWallet> {
+    let path = args
+        .key_file
+        .as_ref()
+        .context("this command signs a request and requires --key-file")?;
+    let key = std::fs::read_to_string(path)
+        .with_context(|| format!("reading key file {}", path.display()))?;
+    let wallet = LocalWallet::from_str(key.trim())
+        .with_context(|| format!("parsing key file {}", path.display()))?;
+    Ok(wallet)
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        core::utils::Anvil,
+        middleware::{Middleware, SignerMiddleware},
+        providers::{Provider, Ws},
+        signers::Signer as _,
+        types::TransactionRequest,
+        utils::{parse_ether, AnvilInstance},
+    };
+    use lib_didethresolver::did_registry::DIDRegistry;
+    use lib_xps::{config::Contracts, RunningGateway, ServerOptions};
+    use std::{sync::Arc, time::Duration};
+
+    const ENDPOINT: &str = "ws://127.0.0.1:1";
+
+    /// A gateway running against a fresh anvil network with the registry deployed
+    struct TestGateway {
+        anvil: AnvilInstance,
+        gateway: RunningGateway<Provider<Ws>>,
+    }
+
+    impl TestGateway {
+        async fn start() -> Result<Self> {
+            let anvil = Anvil::new().args(vec!["--base-fee", "100"]).spawn();
+            let wallet: LocalWallet = anvil.keys()[0].clone().into();
+            let deployer = Arc::new(SignerMiddleware::new(
+                provider(&anvil).await?,
+                wallet.with_chain_id(anvil.chain_id()),
+            ));
+            let registry = DIDRegistry::deploy(deployer.clone(), ())?
+                .gas_price(100)
+                .send()
+                .await?;
+
+            // messaging is not exercised, so no conversation contract is deployed
+            let contracts = Contracts {
+                registry: registry.address(),
+                conversation: Address::zero(),
+            };
+            let gateway = lib_xps::start(
+                "127.0.0.1".to_string(),
+                0,
+                provider(&anvil).await?,
+                contracts,
+                ServerOptions::default(),
+            )
+            .await?;
+            let tx = TransactionRequest::pay(gateway.signer, parse_ether(1)?);
+            deployer.send_transaction(tx, None).await?.await?;
+
+            Ok(Self { anvil, gateway })
+        }
+
+        /// Runs the command line `args` against the gateway
+        async fn run(&self, args: &[&str]) -> Result<String> {
+            let endpoint = format!("ws://{}", self.gateway.addr);
+            let args = Args::parse_from(
+                ["xps-cli", "--endpoint", endpoint.as_str()]
+                    .iter()
+                    .chain(args),
+            );
+            let gateway = Gateway::connect(&args.endpoint).await?;
+            execute(&gateway, &args).await
+        }
+
+        /// Writes the private key of the anvil account at `index` to a key file
+        fn key_file(&self, index: usize) -> Result<PathBuf> {
+            let path = std::env::temp_dir().join(format!(
+                "xps-cli-key-{}-{}.hex",
+                std::process::id(),
+                index
+            ));
+            std::fs::write(&path, hex::encode(self.anvil.keys()[index].to_bytes()))?;
+            Ok(path)
+        }
+
+        fn address(&self, index: usize) -> String {
+            format!("{:#x}", self.anvil.addresses()[index])
+        }
+    }
+
+    async fn provider(anvil: &AnvilInstance) -> Result<Provider<Ws>> {
+        Ok(Provider::<Ws>::connect(anvil.ws_endpoint())
+            .await?
+            .interval(Duration::from_millis(10)))
+    }
+
+    #[test]
+    fn test_endpoint_required() {
+        assert!(Args::try_parse_from(vec!["xps-cli", "status"]).is_err());
+    }
+
+    #[test]
+    fn test_default() -> Result<()> {
+        let args = Args::parse_from(vec!["xps-cli", "-e", ENDPOINT, "status"]);
+        assert_eq!(args.endpoint, ENDPOINT);
+        assert!(args.key_file.is_none());
+        assert!(!args.json);
+        assert!(matches!(args.command, Command::Status));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<()> {
+        let args = Args::parse_from(vec!["xps-cli", "-e", ENDPOINT, "--json", "wallet-address"]);
+        assert!(args.json);
+        assert!(matches!(args.command, Command::WalletAddress));
+        Ok(())
+    }
+
+    #[test]
+    fn test_grant() -> Result<()> {
+        let args = Args::parse_from(vec![
+            "xps-cli", "-e", ENDPOINT, "-k", "key.hex", "grant", "0x0102",
+        ]);
+        assert_eq!(args.key_file, Some(PathBuf::from("key.hex")));
+        assert!(matches!(args.command, Command::Grant { key_package } if key_package == "0x0102"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_send() -> Result<()> {
+        let args = Args::parse_from(vec![
+            "xps-cli",
+            "-e",
+            ENDPOINT,
+            "send",
+            "--conversation",
+            "0x00",
+            "0xdeadbeef",
+        ]);
+        match args.command {
+            Command::Send {
+                conversation,
+                payload,
+            } => {
+                assert_eq!(conversation, "0x00");
+                assert_eq!(payload, Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]));
+            }
+            _ => panic!("expected send"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_wallet_requires_key_file() {
+        let args = Args::parse_from(vec!["xps-cli", "-e", ENDPOINT, "revoke", "0x01"]);
+        assert!(wallet(&args).is_err());
+    }
+
+    #[test]
+    fn test_decode_hex() -> Result<()> {
+        assert_eq!(decode_hex("0x0102")?, vec![1, 2]);
+        assert_eq!(decode_hex("0102")?, vec![1, 2]);
+        assert!(decode_hex("0xzz").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gateway_queries() -> Result<()> {
+        let gateway = TestGateway::start().await?;
+
+        assert_eq!(gateway.run(&["status"]).await?, "OK");
+        assert_eq!(
+            gateway.run(&["wallet-address"]).await?,
+            format!("{:#x}", gateway.gateway.signer)
+        );
+        assert_eq!(gateway.run(&["balance"]).await?, "1.000000000000000000 ETH");
+        assert_eq!(gateway.run(&["nonce", &gateway.address(1)]).await?, "0");
+        assert_eq!(
+            gateway
+                .run(&["--json", "fetch-key-packages", &gateway.address(1)])
+                .await?,
+            "[]"
+        );
+
+        gateway.gateway.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gateway_grant_revoke() -> Result<()> {
+        let gateway = TestGateway::start().await?;
+        let key_file = gateway.key_file(2)?;
+        let key_file = key_file.to_str().unwrap();
+        let did = gateway.address(2);
+
+        let granted: serde_json::Value = serde_json::from_str(
+            &gateway
+                .run(&["--json", "-k", key_file, "grant", "0x0102"])
+                .await?,
+        )?;
+        assert_eq!(granted["status"], "Success");
+        assert_eq!(gateway.run(&["fetch-key-packages", &did]).await?, "0x0102");
+        assert_eq!(gateway.run(&["nonce", &did]).await?, "1");
+
+        let revoked = gateway.run(&["-k", key_file, "revoke", "0x0102"]).await?;
+        let transaction = revoked
+            .strip_prefix("Installation revoked. (success, transaction 0x")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap_or_else(|| panic!("unexpected output {revoked}"));
+        assert_eq!(transaction.len(), 64);
+        assert_eq!(gateway.run(&["fetch-key-packages", &did]).await?, "");
+
+        std::fs::remove_file(key_file)?;
+        gateway.gateway.shutdown().await?;
+        Ok(())
+    }
+}
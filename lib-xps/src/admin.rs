@@ -0,0 +1,173 @@
+//! Runtime controls of the gateway, for its operators.
+//!
+//! The `admin` JSON-RPC namespace, see [`crate::rpc::admin`], is served on a port of its own,
+//! bound to localhost by default. It may only bind to another address along with TLS, which it
+//! then serves with the certificate of the public server. With a client CA, every request to it
+//! must come with a client certificate signed by that CA. Every request must also be signed by
+//! one of the operators of the [`AuthPolicy`](crate::auth::AuthPolicy), so the admin server
+//! refuses to start without one. API keys are rejected. The metrics, see [`crate::metrics`], are
+//! served there as well.
+//!
+//! Pausing the gateway makes the public server reject the methods changing state, see
+//! [`is_mutating`], while reads keep being served.
+//!
+//! Funds of the gateway wallet may only be moved out to the treasury address configured here,
+//! never to an address given in the request.
+
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use ethers::types::Address;
+use jsonrpsee::{
+    server::middleware::rpc::RpcServiceT,
+    types::{error::SERVER_IS_BUSY_CODE, ErrorObject, Request},
+    MethodResponse,
+};
+
+use crate::{auth::Authenticator, tls::TlsConfig};
+
+/// Host the admin server binds to by default
+pub const DEFAULT_ADMIN_HOST: &str = "127.0.0.1";
+
+/// Methods of the `xps` and `xps_v2` namespaces which change state, without the namespace
+const MUTATING_METHODS: &[&str] = &[
+    "sendMessage",
+    "grantInstallation",
+    "revokeInstallation",
+    "renewInstallation",
+    "batchInstallations",
+    "addDelegate",
+    "revokeDelegate",
+    "changeOwner",
+    "deactivateDid",
+];
+
+/// Where to serve the `admin` namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminOptions {
+    pub host: String,
+    pub port: u16,
+    /// Where `admin_withdraw` and `admin_sweep` send funds, disabled if none
+    pub treasury: Option<Address>,
+}
+
+impl Default for AdminOptions {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_ADMIN_HOST.to_string(),
+            port: 0,
+            treasury: None,
+        }
+    }
+}
+
+/// The state of the gateway operators act on through the `admin` namespace
+#[derive(Debug, Clone)]
+pub struct Controls {
+    pub pause: Arc<Pause>,
+    pub authenticator: Arc<Authenticator>,
+    /// File the auth policy was read from, reread on reload
+    pub auth_file: Option<PathBuf>,
+    /// Certificates of the public server, reread on reload
+    pub tls: Option<Arc<TlsConfig>>,
+    /// Address funds may be moved to
+    pub treasury: Option<Address>,
+}
+
+/// Whether `method` belongs to the `xps` or `xps_v2` namespace and changes state
+pub fn is_mutating(method: &str) -> bool {
+    method
+        .strip_prefix("xps_v2_")
+        .or_else(|| method.strip_prefix("xps_"))
+        .map_or(false, |name| MUTATING_METHODS.contains(&name))
+}
+
+/// Whether the methods changing state are paused
+#[derive(Debug, Default)]
+pub struct Pause {
+    paused: AtomicBool,
+}
+
+impl Pause {
+    /// Pauses the methods changing state, returning whether they were paused already
+    pub fn pause(&self) -> bool {
+        self.paused.swap(true, Ordering::SeqCst)
+    }
+
+    /// Resumes the methods changing state, returning whether they were paused
+    pub fn resume(&self) -> bool {
+        self.paused.swap(false, Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+}
+
+/// RPC middleware rejecting the methods changing state while paused
+#[derive(Debug, Clone)]
+pub struct Pausable<S> {
+    inner: S,
+    pause: Arc<Pause>,
+}
+
+impl<S> Pausable<S> {
+    pub fn new(inner: S, pause: Arc<Pause>) -> Self {
+        Self { inner, pause }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for Pausable<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        if self.pause.is_paused() && is_mutating(request.method_name()) {
+            let error = ErrorObject::owned(
+                SERVER_IS_BUSY_CODE,
+                "Methods changing state are paused by the operator",
+                None::<()>,
+            );
+            let response = MethodResponse::error(request.id, error);
+            return Box::pin(async move { response });
+        }
+        let service = self.inner.clone();
+        Box::pin(async move { service.call(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_mutating() {
+        assert!(is_mutating("xps_sendMessage"));
+        assert!(is_mutating("xps_v2_grantInstallation"));
+        assert!(is_mutating("xps_deactivateDid"));
+        assert!(!is_mutating("xps_status"));
+        assert!(!is_mutating("xps_v2_resolveDid"));
+        assert!(!is_mutating("admin_pause"));
+        assert!(!is_mutating("sendMessage"));
+    }
+
+    #[test]
+    fn test_pause() {
+        let pause = Pause::default();
+        assert!(!pause.is_paused());
+        assert!(!pause.pause());
+        assert!(pause.pause());
+        assert!(pause.is_paused());
+        assert!(pause.resume());
+        assert!(!pause.is_paused());
+    }
+}
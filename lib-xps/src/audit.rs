@@ -0,0 +1,9 @@
+//! Audit trail for irreversible operations relayed by the gateway.
+//!
+//! Entries are emitted on the `xps::audit` tracing target, so they can be routed to a
+//! dedicated sink with a filter such as `RUST_LOG=xps::audit=info`.
+
+/// Records that `action` was performed on `subject`, along with its `outcome`
+pub fn record(action: &str, subject: &str, outcome: &str) {
+    tracing::info!(target: "xps::audit", action, subject, outcome, "audit");
+}
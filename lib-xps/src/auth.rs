@@ -0,0 +1,895 @@
+//! Optional authentication of the clients of the gateway.
+//!
+//! Relaying a request costs the gateway ETH, so a gateway reachable by untrusted clients may
+//! require every HTTP request, and every websocket upgrade, to carry credentials. Two kinds of
+//! credentials are accepted, as configured by an [`AuthPolicy`]:
+//!
+//! * a static API key, sent as `Authorization: Bearer <key>`, optionally limited to a quota of
+//!   calls per window. The quota is counted by the [`CallQuota`] RPC middleware, per call and per
+//!   entry of a batch, so calls made over a websocket connection count as much as HTTP requests.
+//! * a signature by an allowlisted operator key, sent as the `X-XPS-Timestamp` (unix seconds),
+//!   `X-XPS-Nonce` and `X-XPS-Signature` (hex encoded) headers. The operator signs, as an EIP-191
+//!   personal message, the [`SignedRequest::message`] binding the timestamp, the nonce, the HTTP
+//!   method and the path to the body of the request. A nonce is accepted once per operator within
+//!   the allowed clock skew, so a captured request cannot be replayed.
+//!
+//! The authenticated [`Principal`] is attached to the logs of the request and to the
+//! `xps_requests_total` metric. Since the metrics name the principals, only operators may read
+//! them from [`METRICS_PATH`](crate::metrics::METRICS_PATH).
+
+use std::{
+    collections::HashMap,
+    fs,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock, RwLockReadGuard},
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use ethers::types::{Address, Signature};
+use hyper::{
+    header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Body, HeaderMap, Request, Response, StatusCode,
+};
+use jsonrpsee::{server::middleware::rpc::RpcServiceT, types::ErrorObject, MethodResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+use crate::{
+    limits::{read_body, BodyError},
+    metrics::{self, METRICS_PATH},
+};
+
+/// Header holding the time an operator signed the request at
+pub const TIMESTAMP_HEADER: &str = "x-xps-timestamp";
+/// Header holding the operator signature of the request
+pub const SIGNATURE_HEADER: &str = "x-xps-signature";
+/// Header holding the value making a signed request unique
+pub const NONCE_HEADER: &str = "x-xps-nonce";
+
+/// Code of the JSON-RPC error returned once the quota of an API key is exhausted
+pub const QUOTA_EXCEEDED_CODE: i32 = -32005;
+
+/// Maximum length of a nonce, made of ASCII alphanumerics, `-` and `_`
+const MAX_NONCE_LENGTH: usize = 64;
+
+/// Maximum difference between the signed timestamp and the clock of the gateway, by default
+const DEFAULT_MAX_SKEW_SECONDS: u64 = 300;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+tokio::task_local! {
+    /// Principal of the HTTP request or websocket upgrade being served
+    static PRINCIPAL: Principal;
+}
+
+/// Credentials accepted by the gateway
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthPolicy {
+    /// Static API keys
+    #[serde(rename = "apiKeys", default)]
+    pub api_keys: Vec<ApiKey>,
+    /// Operators allowed to sign requests
+    #[serde(default)]
+    pub operators: Vec<Address>,
+    /// Maximum age, or clock skew, of a signed request in seconds. Defaults to 5 minutes.
+    #[serde(rename = "maxSkewSeconds", default = "default_max_skew")]
+    pub max_skew_seconds: u64,
+}
+
+fn default_max_skew() -> u64 {
+    DEFAULT_MAX_SKEW_SECONDS
+}
+
+impl AuthPolicy {
+    /// Reads a JSON policy from `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+/// An API key along with the name it is reported under
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Name of the holder of the key, used in logs and metrics
+    pub name: String,
+    pub key: String,
+    /// Requests allowed with this key, unlimited if none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<Quota>,
+}
+
+/// A number of requests allowed per fixed window of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Quota {
+    pub requests: u64,
+    #[serde(rename = "windowSeconds")]
+    pub window_seconds: u64,
+}
+
+/// The authenticated client of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Principal {
+    /// A holder of the named API key
+    ApiKey(String),
+    /// An allowlisted operator
+    Operator(Address),
+}
+
+impl std::fmt::Display for Principal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Principal::ApiKey(name) => write!(f, "key:{name}"),
+            Principal::Operator(address) => write!(f, "operator:{address:?}"),
+        }
+    }
+}
+
+/// Reasons a request is refused
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Missing credentials")]
+    MissingCredentials,
+    #[error("Unknown API key")]
+    UnknownKey,
+    #[error("Quota of {0} calls exceeded")]
+    QuotaExceeded(u64),
+    #[error("Malformed signature headers")]
+    MalformedSignature,
+    #[error("Signed timestamp is too far from the current time")]
+    Expired,
+    #[error("Signed request already seen")]
+    Replayed,
+    #[error("Request not signed by an allowed operator")]
+    UnknownOperator,
+    #[error("Signed request body larger than {0} bytes")]
+    TooLarge(u32),
+    #[error("Only operators may call this endpoint")]
+    OperatorRequired,
+}
+
+impl AuthError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AuthError::OperatorRequired => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// Label of the error in the `xps_auth_rejected_total` metric
+    fn reason(&self) -> &'static str {
+        match self {
+            AuthError::MissingCredentials => "missing",
+            AuthError::UnknownKey => "unknown_key",
+            AuthError::QuotaExceeded(_) => "quota",
+            AuthError::MalformedSignature => "malformed_signature",
+            AuthError::Expired => "expired",
+            AuthError::Replayed => "replayed",
+            AuthError::UnknownOperator => "unknown_operator",
+            AuthError::TooLarge(_) => "too_large",
+            AuthError::OperatorRequired => "operator_required",
+        }
+    }
+}
+
+/// Usage of an API key within its current quota window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct KeyUsage {
+    pub name: String,
+    /// Calls made in the current window
+    pub requests: u64,
+    pub quota: Option<Quota>,
+}
+
+/// The parts of a request an operator signs
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest<'a> {
+    /// Unix time in seconds, as sent in the `X-XPS-Timestamp` header
+    pub timestamp: &'a str,
+    /// Unique value of the request, as sent in the `X-XPS-Nonce` header
+    pub nonce: &'a str,
+    /// HTTP method, e.g. `POST`
+    pub method: &'a str,
+    pub path: &'a str,
+    pub body: &'a [u8],
+}
+
+impl SignedRequest<'_> {
+    /// The message an operator signs: the timestamp, the nonce, then the method and path, each
+    /// followed by a newline, then the body
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = format!(
+            "{}\n{}\n{} {}\n",
+            self.timestamp, self.nonce, self.method, self.path
+        )
+        .into_bytes();
+        message.extend_from_slice(self.body);
+        message
+    }
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    requests: u64,
+}
+
+/// Checks credentials against an [`AuthPolicy`] and keeps track of quota usage
+#[derive(Debug)]
+pub struct Authenticator {
+    policy: RwLock<AuthPolicy>,
+    usage: Mutex<HashMap<String, Window>>,
+    /// Nonces accepted from each operator, with the time they were signed at
+    seen: Mutex<HashMap<(Address, String), u64>>,
+}
+
+impl Authenticator {
+    pub fn new(policy: AuthPolicy) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+            usage: Mutex::new(HashMap::new()),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the policy. Usage of the API keys kept by name carries over.
+    pub fn reload(&self, policy: AuthPolicy) {
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// The policy currently enforced
+    pub fn policy(&self) -> RwLockReadGuard<'_, AuthPolicy> {
+        self.policy.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Authenticates a request with an API key. Its calls are counted by [`Self::charge`].
+    pub fn authenticate_key(&self, key: &str) -> Result<Principal, AuthError> {
+        let policy = self.policy();
+        let api_key = policy
+            .api_keys
+            .iter()
+            .find(|k| constant_time_eq(k.key.as_bytes(), key.as_bytes()))
+            .ok_or(AuthError::UnknownKey)?;
+        Ok(Principal::ApiKey(api_key.name.clone()))
+    }
+
+    /// Counts a call against the quota of the API key `name`. Fails if the key was removed by a
+    /// reload since the client authenticated.
+    pub fn charge(&self, name: &str) -> Result<(), AuthError> {
+        let policy = self.policy();
+        let api_key = policy
+            .api_keys
+            .iter()
+            .find(|k| k.name == name)
+            .ok_or(AuthError::UnknownKey)?;
+
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let window = usage.entry(api_key.name.clone()).or_insert(Window {
+            started: Instant::now(),
+            requests: 0,
+        });
+        if let Some(quota) = api_key.quota {
+            if window.started.elapsed() >= Duration::from_secs(quota.window_seconds) {
+                window.started = Instant::now();
+                window.requests = 0;
+            }
+            if window.requests >= quota.requests {
+                return Err(AuthError::QuotaExceeded(quota.requests));
+            }
+        }
+        window.requests += 1;
+        Ok(())
+    }
+
+    /// Authenticates a request signed by an operator, accepting each nonce once
+    pub fn authenticate_signature(
+        &self,
+        request: &SignedRequest<'_>,
+        signature: &str,
+    ) -> Result<Principal, AuthError> {
+        let signed_at = request
+            .timestamp
+            .parse::<u64>()
+            .map_err(|_| AuthError::MalformedSignature)?;
+        if !is_valid_nonce(request.nonce) {
+            return Err(AuthError::MalformedSignature);
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let policy = self.policy();
+        if now.abs_diff(signed_at) > policy.max_skew_seconds {
+            return Err(AuthError::Expired);
+        }
+
+        let signature =
+            Signature::from_str(signature).map_err(|_| AuthError::MalformedSignature)?;
+        let operator = signature
+            .recover(request.message())
+            .map_err(|_| AuthError::MalformedSignature)?;
+        if !policy.operators.contains(&operator) {
+            return Err(AuthError::UnknownOperator);
+        }
+
+        // nonces signed outside the skew are rejected as expired, so need not be kept
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, signed_at| now.abs_diff(*signed_at) <= policy.max_skew_seconds);
+        if seen
+            .insert((operator, request.nonce.to_string()), signed_at)
+            .is_some()
+        {
+            return Err(AuthError::Replayed);
+        }
+        Ok(Principal::Operator(operator))
+    }
+
+    /// Usage of every API key, within its current window
+    pub fn usage(&self) -> Vec<KeyUsage> {
+        let policy = self.policy();
+        let usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        policy
+            .api_keys
+            .iter()
+            .map(|key| {
+                let requests = usage
+                    .get(&key.name)
+                    .filter(|w| {
+                        key.quota.map_or(true, |q| {
+                            w.started.elapsed() < Duration::from_secs(q.window_seconds)
+                        })
+                    })
+                    .map_or(0, |w| w.requests);
+                KeyUsage {
+                    name: key.name.clone(),
+                    requests,
+                    quota: key.quota,
+                }
+            })
+            .collect()
+    }
+}
+
+fn is_valid_nonce(nonce: &str) -> bool {
+    (1..=MAX_NONCE_LENGTH).contains(&nonce.len())
+        && nonce
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+/// Layer authenticating requests before they reach the JSON-RPC server. Without an
+/// [`Authenticator`], requests are passed through unauthenticated.
+#[derive(Debug, Clone)]
+pub struct AuthLayer {
+    authenticator: Option<Arc<Authenticator>>,
+    max_body_size: u32,
+    operators_only: bool,
+}
+
+impl AuthLayer {
+    /// Authenticates requests with `authenticator`, reading signed bodies of up to
+    /// `max_body_size` bytes
+    pub fn new(authenticator: Option<Arc<Authenticator>>, max_body_size: u32) -> Self {
+        Self {
+            authenticator,
+            max_body_size,
+            operators_only: false,
+        }
+    }
+
+    /// Authenticates requests with `authenticator`, rejecting any client but operators
+    pub fn operators_only(authenticator: Arc<Authenticator>, max_body_size: u32) -> Self {
+        Self {
+            authenticator: Some(authenticator),
+            max_body_size,
+            operators_only: true,
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = Auth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Auth {
+            inner,
+            authenticator: self.authenticator.clone(),
+            max_body_size: self.max_body_size,
+            operators_only: self.operators_only,
+        }
+    }
+}
+
+/// Service rejecting unauthenticated requests, see [`AuthLayer`]
+#[derive(Debug, Clone)]
+pub struct Auth<S> {
+    inner: S,
+    authenticator: Option<Arc<Authenticator>>,
+    max_body_size: u32,
+    operators_only: bool,
+}
+
+impl<S> Service<Request<Body>> for Auth<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<BoxError> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let Some(authenticator) = self.authenticator.clone() else {
+            let response = self.inner.call(request);
+            return Box::pin(async move { response.await.map_err(Into::into) });
+        };
+
+        // take the service which was polled ready, leaving a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_body_size = self.max_body_size;
+        let operators_only = self.operators_only;
+        Box::pin(async move {
+            let headers = request.headers();
+            let (principal, request) = if let Some(key) = bearer(headers) {
+                (authenticator.authenticate_key(key), request)
+            } else if let (Some(timestamp), Some(nonce), Some(signature)) = (
+                header(headers, TIMESTAMP_HEADER),
+                header(headers, NONCE_HEADER),
+                header(headers, SIGNATURE_HEADER),
+            ) {
+                let (timestamp, nonce, signature) = (
+                    timestamp.to_string(),
+                    nonce.to_string(),
+                    signature.to_string(),
+                );
+                let (parts, body) = request.into_parts();
+                match read_body(body, max_body_size).await {
+                    Ok(body) => {
+                        let signed = SignedRequest {
+                            timestamp: &timestamp,
+                            nonce: &nonce,
+                            method: parts.method.as_str(),
+                            path: parts.uri.path(),
+                            body: &body,
+                        };
+                        let principal = authenticator.authenticate_signature(&signed, &signature);
+                        (principal, Request::from_parts(parts, Body::from(body)))
+                    }
+                    Err(BodyError::TooLarge(limit)) => (
+                        Err(AuthError::TooLarge(limit)),
+                        Request::from_parts(parts, Body::empty()),
+                    ),
+                    Err(BodyError::Hyper(e)) => return Err(e.into()),
+                }
+            } else {
+                (Err(AuthError::MissingCredentials), request)
+            };
+
+            let principal = match principal {
+                Ok(Principal::ApiKey(_))
+                    if operators_only || request.uri().path() == METRICS_PATH =>
+                {
+                    Err(AuthError::OperatorRequired)
+                }
+                principal => principal,
+            };
+            let principal = match principal {
+                Ok(principal) => principal.to_string(),
+                Err(e) => {
+                    log::debug!("rejected request: {e}");
+                    metrics::increment("xps_auth_rejected_total", &[("reason", e.reason())]);
+                    return Ok(reject(&e));
+                }
+            };
+
+            let label = principal.to_string();
+            metrics::increment("xps_requests_total", &[("principal", &label)]);
+            let span = tracing::info_span!("request", principal = %label);
+            // the RPC middleware of the request, or of the upgraded connection, is built within
+            // this scope and picks the principal up, see `CallQuota::new`
+            PRINCIPAL
+                .scope(principal, inner.call(request).instrument(span))
+                .await
+                .map_err(Into::into)
+        })
+    }
+}
+
+/// RPC middleware counting every call, including each entry of a batch, against the quota of
+/// the API key its connection authenticated with
+#[derive(Debug, Clone)]
+pub struct CallQuota<S> {
+    inner: S,
+    authenticator: Option<Arc<Authenticator>>,
+    key: Option<String>,
+}
+
+impl<S> CallQuota<S> {
+    /// Must be built while serving the HTTP request or websocket upgrade, as jsonrpsee does for
+    /// the RPC middleware of each, to find out which API key to charge
+    pub fn new(inner: S, authenticator: Option<Arc<Authenticator>>) -> Self {
+        let key = PRINCIPAL
+            .try_with(|principal| match principal {
+                Principal::ApiKey(name) => Some(name.clone()),
+                Principal::Operator(_) => None,
+            })
+            .ok()
+            .flatten();
+        Self {
+            inner,
+            authenticator,
+            key,
+        }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for CallQuota<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: jsonrpsee::types::Request<'a>) -> Self::Future {
+        if let (Some(authenticator), Some(key)) = (&self.authenticator, &self.key) {
+            if let Err(e) = authenticator.charge(key) {
+                metrics::increment("xps_auth_rejected_total", &[("reason", e.reason())]);
+                let error = ErrorObject::owned(QUOTA_EXCEEDED_CODE, e.to_string(), None::<()>);
+                let response = MethodResponse::error(request.id, error);
+                return Box::pin(async move { response });
+            }
+        }
+        let service = self.inner.clone();
+        Box::pin(async move { service.call(request).await })
+    }
+}
+
+fn reject(error: &AuthError) -> Response<Body> {
+    let body = json!({ "code": error.status().as_u16(), "message": error.to_string() });
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = error.status();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    fn policy(operator: Address) -> AuthPolicy {
+        AuthPolicy {
+            api_keys: vec![
+                ApiKey {
+                    name: "limited".to_string(),
+                    key: "secret-1".to_string(),
+                    quota: Some(Quota {
+                        requests: 2,
+                        window_seconds: 3600,
+                    }),
+                },
+                ApiKey {
+                    name: "unlimited".to_string(),
+                    key: "secret-2".to_string(),
+                    quota: None,
+                },
+            ],
+            operators: vec![operator],
+            max_skew_seconds: DEFAULT_MAX_SKEW_SECONDS,
+        }
+    }
+
+    fn now() -> String {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    }
+
+    #[test]
+    fn test_api_key_quota() {
+        let authenticator = Authenticator::new(policy(Address::zero()));
+        assert_eq!(
+            authenticator.authenticate_key("secret-1"),
+            Ok(Principal::ApiKey("limited".to_string()))
+        );
+        assert_eq!(
+            authenticator.authenticate_key("secret-3"),
+            Err(AuthError::UnknownKey)
+        );
+        for _ in 0..2 {
+            assert_eq!(authenticator.charge("limited"), Ok(()));
+        }
+        assert_eq!(
+            authenticator.charge("limited"),
+            Err(AuthError::QuotaExceeded(2))
+        );
+        assert_eq!(authenticator.charge("unlimited"), Ok(()));
+        assert_eq!(authenticator.charge("removed"), Err(AuthError::UnknownKey));
+
+        let usage = authenticator.usage();
+        assert_eq!(usage[0].requests, 2);
+        assert_eq!(usage[1].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_signature() {
+        let operator = LocalWallet::new(&mut rand::thread_rng());
+        let stranger = LocalWallet::new(&mut rand::thread_rng());
+        let authenticator = Authenticator::new(policy(operator.address()));
+        let timestamp = now();
+        let request = SignedRequest {
+            timestamp: &timestamp,
+            nonce: "0",
+            method: "POST",
+            path: "/",
+            body: br#"{"jsonrpc":"2.0","id":0,"method":"xps_status"}"#,
+        };
+
+        let signature = operator.sign_message(request.message()).await.unwrap();
+        assert_eq!(
+            authenticator.authenticate_signature(&request, &signature.to_string()),
+            Ok(Principal::Operator(operator.address()))
+        );
+        for tampered in [
+            SignedRequest {
+                nonce: "1",
+                ..request
+            },
+            SignedRequest {
+                body: b"{}",
+                ..request
+            },
+            SignedRequest {
+                path: "/admin",
+                ..request
+            },
+            SignedRequest {
+                method: "GET",
+                ..request
+            },
+        ] {
+            assert_eq!(
+                authenticator.authenticate_signature(&tampered, &signature.to_string()),
+                Err(AuthError::UnknownOperator)
+            );
+        }
+        assert_eq!(
+            authenticator.authenticate_signature(
+                &SignedRequest {
+                    timestamp: "0",
+                    ..request
+                },
+                &signature.to_string()
+            ),
+            Err(AuthError::Expired)
+        );
+        assert_eq!(
+            authenticator.authenticate_signature(
+                &SignedRequest {
+                    nonce: "",
+                    ..request
+                },
+                &signature.to_string()
+            ),
+            Err(AuthError::MalformedSignature)
+        );
+
+        let signature = stranger.sign_message(request.message()).await.unwrap();
+        assert_eq!(
+            authenticator.authenticate_signature(&request, &signature.to_string()),
+            Err(AuthError::UnknownOperator)
+        );
+        assert_eq!(
+            authenticator.authenticate_signature(&request, "0x00"),
+            Err(AuthError::MalformedSignature)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let operator = LocalWallet::new(&mut rand::thread_rng());
+        let authenticator = Authenticator::new(policy(operator.address()));
+        let timestamp = now();
+        let request = SignedRequest {
+            timestamp: &timestamp,
+            nonce: "withdraw-1",
+            method: "POST",
+            path: "/",
+            body: br#"{"jsonrpc":"2.0","id":0,"method":"admin_withdraw"}"#,
+        };
+        let signature = operator.sign_message(request.message()).await.unwrap();
+        assert!(authenticator
+            .authenticate_signature(&request, &signature.to_string())
+            .is_ok());
+        assert_eq!(
+            authenticator.authenticate_signature(&request, &signature.to_string()),
+            Err(AuthError::Replayed)
+        );
+
+        let request = SignedRequest {
+            nonce: "withdraw-2",
+            ..request
+        };
+        let signature = operator.sign_message(request.message()).await.unwrap();
+        assert!(authenticator
+            .authenticate_signature(&request, &signature.to_string())
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        let authenticator = Arc::new(Authenticator::new(policy(Address::zero())));
+        let service = AuthLayer::new(Some(authenticator), 1024).layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(Body::from("OK")))
+        }));
+
+        let request = |key: Option<&str>| {
+            let mut builder = Request::builder().uri("/");
+            if let Some(key) = key {
+                builder = builder.header(AUTHORIZATION, format!("Bearer {key}"));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let response = service.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = service
+            .clone()
+            .oneshot(request(Some("nope")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = service
+            .clone()
+            .oneshot(request(Some("secret-2")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            metrics::get("xps_requests_total", &[("principal", "key:unlimited")]),
+            1
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct Echo;
+
+    impl<'a> RpcServiceT<'a> for Echo {
+        type Future = std::future::Ready<MethodResponse>;
+
+        fn call(&self, request: jsonrpsee::types::Request<'a>) -> Self::Future {
+            std::future::ready(MethodResponse::response(
+                request.id,
+                jsonrpsee::ResponsePayload::result("OK"),
+                usize::MAX,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_quota() {
+        let authenticator = Arc::new(Authenticator::new(policy(Address::zero())));
+        let request = || {
+            jsonrpsee::types::Request::new(
+                "xps_status".into(),
+                None,
+                jsonrpsee::types::Id::Number(0),
+            )
+        };
+
+        let anonymous = CallQuota::new(Echo, Some(authenticator.clone()));
+        let limited = PRINCIPAL.sync_scope(Principal::ApiKey("limited".to_string()), || {
+            CallQuota::new(Echo, Some(authenticator.clone()))
+        });
+        for _ in 0..2 {
+            assert!(limited.call(request()).await.is_success());
+        }
+        let response = limited.call(request()).await;
+        assert!(!response.is_success());
+        assert!(response
+            .as_result()
+            .contains(&QUOTA_EXCEEDED_CODE.to_string()));
+        for _ in 0..3 {
+            assert!(anonymous.call(request()).await.is_success());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operators_only() {
+        let authenticator = Arc::new(Authenticator::new(policy(Address::zero())));
+        let service = AuthLayer::operators_only(authenticator, 1024).layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(Body::from("OK")))
+        }));
+        let request = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, "Bearer secret-2")
+            .body(Body::empty())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_operators_only() {
+        let authenticator = Arc::new(Authenticator::new(policy(Address::zero())));
+        let service = AuthLayer::new(Some(authenticator), 1024).layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(Body::from("OK")))
+        }));
+        let request = |key: Option<&str>| {
+            let mut builder = Request::builder().uri(METRICS_PATH);
+            if let Some(key) = key {
+                builder = builder.header(AUTHORIZATION, format!("Bearer {key}"));
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+        let response = service.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = service.oneshot(request(Some("secret-2"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_reload() {
+        let authenticator = Authenticator::new(policy(Address::zero()));
+        assert!(authenticator.authenticate_key("secret-1").is_ok());
+
+        let mut reloaded = policy(Address::zero());
+        reloaded.api_keys.remove(0);
+        authenticator.reload(reloaded);
+        assert_eq!(
+            authenticator.authenticate_key("secret-1"),
+            Err(AuthError::UnknownKey)
+        );
+        assert_eq!(authenticator.usage()[0].name, "unlimited");
+    }
+
+    #[test]
+    fn test_policy_field_names() {
+        let policy: AuthPolicy = serde_json::from_value(json!({
+            "apiKeys": [{ "name": "a", "key": "k", "quota": { "requests": 10, "windowSeconds": 60 } }],
+            "operators": ["0x0000000000000000000000000000000000000001"]
+        }))
+        .unwrap();
+        assert_eq!(policy.api_keys[0].quota.unwrap().window_seconds, 60);
+        assert_eq!(policy.operators, vec![Address::from_low_u64_be(1)]);
+        assert_eq!(policy.max_skew_seconds, DEFAULT_MAX_SKEW_SECONDS);
+    }
+}
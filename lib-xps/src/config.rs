@@ -0,0 +1,115 @@
+//! Network configuration consumed by the gateway, as written by `localnet`
+
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::Result;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use xps_types::{CONVERSATION, DID_ETH_REGISTRY};
+
+/// Addresses of the contracts the gateway relays transactions to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contracts {
+    /// The did:ethr `DIDRegistry`
+    pub registry: Address,
+    /// The `Conversation` contract
+    pub conversation: Address,
+}
+
+impl Default for Contracts {
+    /// The contracts deployed on Sepolia
+    fn default() -> Self {
+        Self {
+            registry: Address::from_str(DID_ETH_REGISTRY).expect("valid address"),
+            conversation: Address::from_str(CONVERSATION).expect("valid address"),
+        }
+    }
+}
+
+/// A funded account on the network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub address: Address,
+    /// Hex encoded private key
+    #[serde(rename = "privateKey")]
+    pub private_key: String,
+}
+
+/// Everything needed to point the gateway, or a client, at a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    /// HTTP endpoint of the node
+    pub endpoint: String,
+    /// Websocket endpoint of the node, used by the gateway
+    #[serde(rename = "wsEndpoint")]
+    pub ws_endpoint: String,
+    pub contracts: Contracts,
+    pub accounts: Vec<Account>,
+    /// Websocket endpoint of a gateway serving this network, if one was started with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+}
+
+impl NetworkConfig {
+    /// Reads a JSON network configuration from `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Writes this configuration as JSON to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let config = NetworkConfig {
+            chain_id: 31337,
+            endpoint: "http://localhost:8545".to_string(),
+            ws_endpoint: "ws://localhost:8545".to_string(),
+            contracts: Contracts {
+                registry: Address::from_low_u64_be(1),
+                conversation: Address::from_low_u64_be(2),
+            },
+            accounts: vec![Account {
+                address: Address::from_low_u64_be(3),
+                private_key: "0x01".to_string(),
+            }],
+            gateway: Some("ws://127.0.0.1:9944".to_string()),
+        };
+
+        let path = std::env::temp_dir().join(format!("xps-config-{}.json", std::process::id()));
+        config.save(&path)?;
+        let loaded = NetworkConfig::load(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(loaded, config);
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_names() -> Result<()> {
+        let config: NetworkConfig = serde_json::from_value(serde_json::json!({
+            "chainId": 1,
+            "endpoint": "http://localhost:8545",
+            "wsEndpoint": "ws://localhost:8545",
+            "contracts": {
+                "registry": "0xd1D374DDE031075157fDb64536eF5cC13Ae75000",
+                "conversation": "0x15aE865d0645816d8EEAB0b7496fdd24227d1801"
+            },
+            "accounts": []
+        }))?;
+        assert_eq!(config.contracts, Contracts::default());
+        assert!(config.gateway.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! Cross-origin resource sharing, so that browser-based clients may call the gateway.
+//!
+//! CORS is disabled unless origins are configured, in which case browsers are allowed to call
+//! the JSON-RPC and REST endpoints from those origins only, or from any origin with `*`.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use hyper::{
+    header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::auth::{NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// How long browsers may cache the answer to a preflight request
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// CORS layer allowing `origins`, or `None` if no origin is allowed
+pub fn layer(origins: &[String]) -> Result<Option<CorsLayer>> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin).map_err(|_| anyhow!("invalid CORS origin {origin}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([
+                CONTENT_TYPE,
+                AUTHORIZATION,
+                HeaderName::from_static(TIMESTAMP_HEADER),
+                HeaderName::from_static(NONCE_HEADER),
+                HeaderName::from_static(SIGNATURE_HEADER),
+            ])
+            .max_age(MAX_AGE),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN},
+        Body, Request, Response, StatusCode,
+    };
+    use std::convert::Infallible;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    async fn preflight(origins: &[&str], origin: &str) -> Response<Body> {
+        let origins = origins.iter().map(|o| o.to_string()).collect::<Vec<_>>();
+        let service = layer(&origins)
+            .unwrap()
+            .unwrap()
+            .layer(service_fn(|_| async {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .header(ORIGIN, origin)
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap();
+        service.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_allowed_origin() {
+        let response = preflight(&["https://app.example"], "https://app.example").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example"
+        );
+
+        let response = preflight(&["*"], "https://other.example").await;
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin() {
+        let response = preflight(&["https://app.example"], "https://evil.example").await;
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[test]
+    fn test_disabled() {
+        assert!(layer(&[]).unwrap().is_none());
+        assert!(layer(&["bad\norigin".to_string()]).is_err());
+    }
+}
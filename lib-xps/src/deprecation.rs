@@ -0,0 +1,109 @@
+//! Deprecation of the version 1 `xps` namespace in favor of `xps_v2`.
+//!
+//! Calls to a version 1 method are still served, but counted in the
+//! `xps_deprecated_calls_total` metric and logged on the `xps::deprecation` target: as a warning
+//! the first time each method is called, then at debug level. Only the methods registered by
+//! [`XpsServer`](crate::XpsServer) are counted, so that calls to unknown methods cannot add
+//! labels to the metric.
+
+use std::{collections::HashSet, sync::Arc};
+
+use jsonrpsee::{server::middleware::rpc::RpcServiceT, types::Request, RpcModule};
+
+use crate::metrics;
+
+/// Name of the counter of calls to deprecated methods, labelled by method
+pub const DEPRECATED_CALLS: &str = "xps_deprecated_calls_total";
+
+/// Names of the methods of the deprecated version 1 namespace
+pub type DeprecatedMethods = Arc<HashSet<&'static str>>;
+
+/// The methods registered by `v1`, the module serving the version 1 namespace
+pub fn deprecated_methods<M>(v1: &RpcModule<M>) -> DeprecatedMethods {
+    Arc::new(v1.method_names().collect())
+}
+
+/// RPC middleware recording calls to deprecated methods
+#[derive(Debug, Clone)]
+pub struct Deprecation<S> {
+    inner: S,
+    methods: DeprecatedMethods,
+}
+
+impl<S> Deprecation<S> {
+    pub fn new(inner: S, methods: DeprecatedMethods) -> Self {
+        Self { inner, methods }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for Deprecation<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = S::Future;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let method = request.method_name();
+        if self.methods.contains(method) {
+            let calls = metrics::increment(DEPRECATED_CALLS, &[("method", method)]);
+            let replacement = method.replacen("xps_", "xps_v2_", 1);
+            if calls == 1 {
+                log::warn!(
+                    target: "xps::deprecation",
+                    "{method} is deprecated and will be removed, use {replacement} instead"
+                );
+            } else {
+                log::debug!(target: "xps::deprecation", "deprecated {method} called");
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct Served;
+
+    impl<'a> RpcServiceT<'a> for Served {
+        type Future = std::future::Ready<jsonrpsee::MethodResponse>;
+
+        fn call(&self, request: Request<'a>) -> Self::Future {
+            std::future::ready(jsonrpsee::MethodResponse::response(
+                request.id,
+                jsonrpsee::ResponsePayload::result(()),
+                usize::MAX,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_counts_registered_methods_only() {
+        let mut v1 = RpcModule::new(());
+        v1.register_method("xps_deprecationTest", |_, _| serde_json::Value::Null)
+            .unwrap();
+        let service = Deprecation::new(Served, deprecated_methods(&v1));
+        let call = |method: &'static str| {
+            Request::new(method.into(), None, jsonrpsee::types::Id::Number(0))
+        };
+
+        service.call(call("xps_deprecationTest")).await;
+        service.call(call("xps_deprecationTest")).await;
+        service.call(call("xps_deprecationUnknown")).await;
+        service.call(call("xps_v2_deprecationTest")).await;
+        assert_eq!(
+            metrics::get(DEPRECATED_CALLS, &[("method", "xps_deprecationTest")]),
+            2
+        );
+        assert_eq!(
+            metrics::get(DEPRECATED_CALLS, &[("method", "xps_deprecationUnknown")]),
+            0
+        );
+        assert_eq!(
+            metrics::get(DEPRECATED_CALLS, &[("method", "xps_v2_deprecationTest")]),
+            0
+        );
+    }
+}
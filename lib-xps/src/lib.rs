@@ -1,39 +1,396 @@
+pub mod admin;
+pub mod audit;
+pub mod auth;
+pub mod config;
+pub mod cors;
+pub mod deprecation;
+pub mod limits;
+pub mod metrics;
+pub mod openrpc;
+pub mod pending;
+pub mod rest;
 pub mod rpc;
+pub mod shutdown;
+pub mod tls;
 pub mod types;
 #[cfg(test)]
 mod util;
 
-use anyhow::Result;
-use ethers::{abi::Address, providers::Middleware};
-use jsonrpsee::{server::Server, RpcModule};
-use std::str::FromStr;
-use xps_types::{CONVERSATION, DID_ETH_REGISTRY};
+use anyhow::{anyhow, Result};
+use ethers::{providers::Middleware, types::Address};
+use jsonrpsee::{
+    server::{middleware::rpc::RpcServiceBuilder, stop_channel, Server, ServerHandle},
+    RpcModule,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
 
 pub use crate::rpc::{XpsClient, XpsMethods, XpsServer};
-use crate::types::GatewayContext;
+use crate::{
+    admin::{AdminOptions, Controls, Pause},
+    auth::{AuthPolicy, Authenticator},
+    config::Contracts,
+    limits::Limits,
+    rpc::admin::{AdminMethods, AdminServer},
+    shutdown::{Drain, ShutdownSummary, DEFAULT_DRAIN_TIMEOUT},
+    tls::{TlsConfig, TlsOptions},
+    types::{GatewayContext, GatewaySigner},
+};
 
-/// Entrypoint for the xps Gateway
+/// Entrypoint for the xps Gateway, relaying to the contracts deployed on Sepolia
 pub async fn run<P>(host: String, port: u16, provider: P) -> Result<()>
 where
     P: Middleware + 'static,
 {
-    let server_addr = format!("{}:{}", host, port);
-    let server = Server::builder().build(server_addr).await?;
-    let addr = server.local_addr()?;
+    run_with_contracts(
+        host,
+        port,
+        provider,
+        Contracts::default(),
+        ServerOptions::default(),
+    )
+    .await
+}
+
+/// Entrypoint for the xps Gateway, relaying to `contracts`
+pub async fn run_with_contracts<P>(
+    host: String,
+    port: u16,
+    provider: P,
+    contracts: Contracts,
+    options: ServerOptions,
+) -> Result<()>
+where
+    P: Middleware + 'static,
+{
+    let gateway = start(host, port, provider, contracts, options).await?;
+    tokio::select! {
+        _ = gateway.handle.clone().stopped() => return Ok(()),
+        signal = shutdown::signal() => log::info!("Received {}, shutting down", signal?),
+    }
+    gateway.shutdown().await?;
+    Ok(())
+}
 
-    let registry_contract = Address::from_str(DID_ETH_REGISTRY)?;
-    let conversation_contract = Address::from_str(CONVERSATION)?;
+/// Options of the JSON-RPC server, all disabled by default
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// Credentials required from clients, if any. See [`auth`].
+    pub auth: Option<AuthPolicy>,
+    /// File `auth` was read from, read again by `admin_reloadPolicies`
+    pub auth_file: Option<PathBuf>,
+    /// Certificate to serve HTTPS and WSS with, instead of plain HTTP and WS. See [`tls`].
+    pub tls: Option<TlsOptions>,
+    /// Origins browsers may call the gateway from, `*` for any. See [`cors`].
+    pub cors_origins: Vec<String>,
+    pub limits: Limits,
+    /// Time given to in-flight calls to complete on shutdown. See [`shutdown`].
+    pub drain_timeout: Duration,
+    /// Where to write the transactions still pending on shutdown, instead of only logging them
+    pub pending_transactions_file: Option<PathBuf>,
+    /// File holding the private key of the gateway wallet, created if missing. Without it, a new
+    /// wallet is generated at every start, and the transactions left pending by a previous run,
+    /// including those in `pending_transactions_file`, can no longer be replaced or cancelled:
+    /// the file is then informational only.
+    pub wallet_key_file: Option<PathBuf>,
+    /// Where to serve the `admin` namespace, if anywhere. Requires operators in `auth`. See
+    /// [`admin`].
+    pub admin: Option<AdminOptions>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            auth: None,
+            auth_file: None,
+            tls: None,
+            cors_origins: Vec::new(),
+            limits: Limits::default(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            pending_transactions_file: None,
+            wallet_key_file: None,
+            admin: None,
+        }
+    }
+}
 
-    let context = GatewayContext::new(registry_contract, conversation_contract, provider).await?;
+/// A gateway serving requests in the background
+pub struct RunningGateway<P: Middleware> {
+    /// The address the server is listening on
+    pub addr: SocketAddr,
+    /// The address of the wallet paying for relayed transactions
+    pub signer: Address,
+    pub handle: ServerHandle,
+    /// The address the `admin` namespace is served on, if enabled
+    pub admin_addr: Option<SocketAddr>,
+    admin_handle: Option<ServerHandle>,
+    client: Arc<GatewaySigner<P>>,
+    drain: Arc<Drain>,
+    drain_timeout: Duration,
+    pending_transactions_file: Option<PathBuf>,
+}
+
+impl<P: Middleware + 'static> RunningGateway<P> {
+    /// Stops the gateway gracefully: new calls are rejected, calls in flight are given until
+    /// the drain timeout to complete, and the transactions still pending afterwards are persisted
+    pub async fn shutdown(self) -> Result<ShutdownSummary> {
+        let deadline = tokio::time::Instant::now() + self.drain_timeout;
+        self.drain.begin();
+        let in_flight = self.drain.in_flight();
+        if in_flight > 0 {
+            log::info!("Waiting for {in_flight} calls to complete");
+        }
+        let interrupted_calls = if self.drain.wait_idle(self.drain_timeout).await {
+            0
+        } else {
+            self.drain.in_flight()
+        };
+        // stopping fails only if the server already stopped
+        let _ = self.handle.stop();
+        self.handle.stopped().await;
+        if let Some(admin) = self.admin_handle {
+            let _ = admin.stop();
+            admin.stopped().await;
+        }
+
+        // receipts are checked within what is left of the drain timeout
+        let pending = self.client.pending();
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        let pending_transactions = pending
+            .prune_within(self.client.as_ref(), remaining)
+            .await
+            .into_iter()
+            .map(|sent| sent.hash)
+            .collect::<Vec<_>>();
+        let pending_file = match self.pending_transactions_file {
+            Some(path) if !pending_transactions.is_empty() => {
+                pending.save(&path)?;
+                Some(path)
+            }
+            _ => None,
+        };
+
+        let summary = ShutdownSummary {
+            interrupted_calls,
+            pending_transactions,
+            pending_file,
+        };
+        log::info!("Server Stopped: {summary}");
+        Ok(summary)
+    }
+}
+
+/// Starts the xps Gateway relaying to `contracts`, without waiting for it to stop
+pub async fn start<P>(
+    host: String,
+    port: u16,
+    provider: P,
+    contracts: Contracts,
+    options: ServerOptions,
+) -> Result<RunningGateway<P>>
+where
+    P: Middleware + 'static,
+{
+    let context = match &options.wallet_key_file {
+        Some(path) => {
+            let wallet = types::load_or_create_wallet(path)?;
+            GatewayContext::with_wallet(
+                contracts.registry,
+                contracts.conversation,
+                provider,
+                wallet,
+            )
+            .await?
+        }
+        None => GatewayContext::new(contracts.registry, contracts.conversation, provider).await?,
+    };
+    let signer = context.signer.address();
+    pending::PendingTracker::prune_every(&context.signer, pending::DEFAULT_PRUNE_INTERVAL);
     let mut methods = RpcModule::new(());
-    methods.merge(rpc::XpsMethods::new(&context).into_rpc())?;
+    let v1 = XpsServer::into_rpc(XpsMethods::new(&context));
+    let deprecated = deprecation::deprecated_methods(&v1);
+    methods.merge(v1)?;
+    methods.merge(rpc::XpsV2Server::into_rpc(XpsMethods::new(&context)))?;
+    let document = openrpc::document();
+    methods.register_method("rpc.discover", move |_, _| document.clone())?;
     let methods = build_rpc_api(methods);
 
-    let handle = server.start(methods);
+    let tls = options.tls.map(TlsConfig::load).transpose()?.map(Arc::new);
+    // without a client CA, no client can present a certificate, so none is required
+    let operator_paths = match &tls {
+        Some(tls) if tls.verifies_clients() => {
+            log::info!(
+                "Client certificates are required for {} and the admin server",
+                metrics::METRICS_PATH
+            );
+            tls::OPERATOR_PATHS
+        }
+        _ => &[],
+    };
+    let authenticator = options
+        .auth
+        .map(|policy| Arc::new(Authenticator::new(policy)));
+    let limits = options.limits;
+    let drain = Arc::new(Drain::default());
+    let pause = Arc::new(Pause::default());
+    let admin = match options.admin {
+        Some(admin) => {
+            let authenticator = authenticator
+                .clone()
+                .filter(|authenticator| !authenticator.policy().operators.is_empty())
+                .ok_or_else(|| {
+                    anyhow!("The admin namespace requires operators in the auth policy")
+                })?;
+            let controls = Controls {
+                pause: pause.clone(),
+                authenticator,
+                auth_file: options.auth_file,
+                tls: tls.clone(),
+                treasury: admin.treasury,
+            };
+            Some(start_admin(&context, admin, controls, limits).await?)
+        }
+        None => None,
+    };
+    let builder = Server::builder()
+        .max_request_body_size(limits.max_request_body_size)
+        .max_response_body_size(limits.max_response_body_size)
+        .max_connections(limits.max_connections)
+        .max_subscriptions_per_connection(limits.max_subscriptions_per_connection)
+        .set_http_middleware(
+            tower::ServiceBuilder::new()
+                .option_layer(cors::layer(&options.cors_origins)?)
+                .layer(tls::RequireClientCertLayer::new(operator_paths))
+                .layer(auth::AuthLayer::new(
+                    authenticator.clone(),
+                    limits.max_request_body_size,
+                ))
+                .layer(metrics::MetricsLayer)
+                .layer(rest::RestLayer::new(limits.max_request_body_size)),
+        )
+        .set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer_fn({
+                    let drain = drain.clone();
+                    move |service| shutdown::Draining::new(service, drain.clone())
+                })
+                .layer_fn(move |service| admin::Pausable::new(service, pause.clone()))
+                .layer_fn(move |service| auth::CallQuota::new(service, authenticator.clone()))
+                .layer_fn(move |service| {
+                    deprecation::Deprecation::new(service, deprecated.clone())
+                }),
+        );
+    let server_addr = format!("{}:{}", host, port);
+
+    let (addr, handle) = match tls {
+        None => {
+            let server = builder.build(server_addr).await?;
+            (server.local_addr()?, server.start(methods))
+        }
+        Some(tls) => {
+            let listener = TcpListener::bind(server_addr).await?;
+            let addr = listener.local_addr()?;
+            tls::reload_on_sighup(tls.clone())?;
+            let (stop_handle, handle) = stop_channel();
+            let service_builder = builder.to_service_builder();
+            tls::serve(
+                listener,
+                tls,
+                stop_handle.clone(),
+                tls::HANDSHAKE_TIMEOUT,
+                move || {
+                    service_builder
+                        .clone()
+                        .build(methods.clone(), stop_handle.clone())
+                },
+            );
+            (addr, handle)
+        }
+    };
 
     log::info!("Server Started at {addr}");
-    handle.stopped().await;
-    Ok(())
+    let (admin_addr, admin_handle) = admin.unzip();
+    Ok(RunningGateway {
+        addr,
+        signer,
+        handle,
+        admin_addr,
+        admin_handle,
+        client: context.signer,
+        drain,
+        drain_timeout: options.drain_timeout,
+        pending_transactions_file: options.pending_transactions_file,
+    })
+}
+
+/// Serves the `admin` namespace, to operators only. Without TLS, the admin server only binds to
+/// a loopback address, as the operators' requests would otherwise be readable in transit. With a
+/// client CA, every request must also come with a client certificate.
+async fn start_admin<P>(
+    context: &GatewayContext<P>,
+    options: AdminOptions,
+    controls: Controls,
+    limits: Limits,
+) -> Result<(SocketAddr, ServerHandle)>
+where
+    P: Middleware + 'static,
+{
+    let authenticator = controls.authenticator.clone();
+    let tls = controls.tls.clone();
+    let operator_paths = match &tls {
+        Some(tls) if tls.verifies_clients() => tls::EVERY_PATH,
+        _ => &[],
+    };
+    let mut methods = AdminServer::into_rpc(AdminMethods::new(context, controls));
+    let document = openrpc::admin_document();
+    methods.register_method("rpc.discover", move |_, _| document.clone())?;
+    let methods = build_rpc_api(methods);
+    let builder = Server::builder()
+        .max_request_body_size(limits.max_request_body_size)
+        .max_response_body_size(limits.max_response_body_size)
+        .set_http_middleware(
+            tower::ServiceBuilder::new()
+                .layer(tls::RequireClientCertLayer::new(operator_paths))
+                .layer(auth::AuthLayer::operators_only(
+                    authenticator,
+                    limits.max_request_body_size,
+                ))
+                .layer(metrics::MetricsLayer),
+        );
+    let server_addr = format!("{}:{}", options.host, options.port);
+
+    let (addr, handle) = match tls {
+        None => {
+            let server = builder.build(server_addr).await?;
+            let addr = server.local_addr()?;
+            if !addr.ip().is_loopback() {
+                return Err(anyhow!(
+                    "The admin server must bind to a loopback address, not {addr}, unless TLS is configured"
+                ));
+            }
+            (addr, server.start(methods))
+        }
+        Some(tls) => {
+            let listener = TcpListener::bind(server_addr).await?;
+            let addr = listener.local_addr()?;
+            let (stop_handle, handle) = stop_channel();
+            let service_builder = builder.to_service_builder();
+            tls::serve(
+                listener,
+                tls,
+                stop_handle.clone(),
+                tls::HANDSHAKE_TIMEOUT,
+                move || {
+                    service_builder
+                        .clone()
+                        .build(methods.clone(), stop_handle.clone())
+                },
+            );
+            (addr, handle)
+        }
+    };
+    log::info!("Admin Server Started at {addr}");
+    Ok((addr, handle))
 }
 
 // create an endpoint that lists all the methods available on the server, at the
@@ -58,8 +415,13 @@ fn build_rpc_api<M: Send + Sync + 'static>(mut rpc_api: RpcModule<M>) -> RpcModu
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethers::{prelude::Provider, types::U64};
+    use ethers::{
+        prelude::Provider,
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, H256, U256, U64},
+    };
     use jsonrpsee::{core::client::ClientT, ws_client::WsClientBuilder};
+    use serde_json::json;
 
     #[tokio::test]
     async fn test_run() -> Result<()> {
@@ -93,14 +455,47 @@ mod tests {
         assert_eq!(
             methods.methods,
             vec![
+                "rpc.discover",
                 "rpc_methods",
+                "xps_addDelegate",
                 "xps_balance",
+                "xps_batchInstallations",
+                "xps_changeOwner",
+                "xps_deactivateDid",
                 "xps_fetchKeyPackages",
                 "xps_grantInstallation",
+                "xps_listExpiringInstallations",
+                "xps_messageNonce",
                 "xps_nonce",
+                "xps_prepareGrantInstallation",
+                "xps_prepareRevokeInstallation",
+                "xps_prepareSendMessage",
+                "xps_renewInstallation",
+                "xps_resolveDid",
+                "xps_revokeDelegate",
                 "xps_revokeInstallation",
                 "xps_sendMessage",
                 "xps_status",
+                "xps_v2_addDelegate",
+                "xps_v2_balance",
+                "xps_v2_batchInstallations",
+                "xps_v2_changeOwner",
+                "xps_v2_deactivateDid",
+                "xps_v2_fetchKeyPackages",
+                "xps_v2_grantInstallation",
+                "xps_v2_listExpiringInstallations",
+                "xps_v2_messageNonce",
+                "xps_v2_nonce",
+                "xps_v2_prepareGrantInstallation",
+                "xps_v2_prepareRevokeInstallation",
+                "xps_v2_prepareSendMessage",
+                "xps_v2_renewInstallation",
+                "xps_v2_resolveDid",
+                "xps_v2_revokeDelegate",
+                "xps_v2_revokeInstallation",
+                "xps_v2_sendMessage",
+                "xps_v2_status",
+                "xps_v2_walletAddress",
                 "xps_walletAddress",
             ]
         );
@@ -109,6 +504,458 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_shutdown() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let gateway = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            ServerOptions::default(),
+        )
+        .await?;
+        let client = WsClientBuilder::default()
+            .build(&format!("ws://{}", gateway.addr))
+            .await?;
+
+        let drain = gateway.drain.clone();
+        drain.begin();
+        let result = client
+            .request::<serde_json::Value, Vec<()>>("rpc_methods", vec![])
+            .await;
+        assert!(result.is_err());
+
+        let summary = gateway.shutdown().await?;
+        assert_eq!(summary, ShutdownSummary::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_operators() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let options = ServerOptions {
+            auth: Some(AuthPolicy::default()),
+            admin: Some(AdminOptions::default()),
+            ..Default::default()
+        };
+        let result = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_rejects_anonymous() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let options = ServerOptions {
+            auth: Some(AuthPolicy {
+                operators: vec![Address::from_low_u64_be(1)],
+                ..Default::default()
+            }),
+            admin: Some(AdminOptions::default()),
+            ..Default::default()
+        };
+        let gateway = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await?;
+        let admin_addr = gateway.admin_addr.unwrap();
+        assert!(admin_addr.ip().is_loopback());
+
+        let client = WsClientBuilder::default()
+            .build(&format!("ws://{admin_addr}"))
+            .await;
+        assert!(client.is_err());
+
+        gateway.shutdown().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quota_counts_calls() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let key = |name: &str, key: &str| auth::ApiKey {
+            name: name.to_string(),
+            key: key.to_string(),
+            quota: Some(auth::Quota {
+                requests: 2,
+                window_seconds: 3600,
+            }),
+        };
+        let options = ServerOptions {
+            auth: Some(AuthPolicy {
+                api_keys: vec![key("ws", "ws-secret"), key("http", "http-secret")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let gateway = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await?;
+
+        let mut headers = hyper::HeaderMap::new();
+        headers.insert(
+            hyper::header::AUTHORIZATION,
+            "Bearer ws-secret".parse().unwrap(),
+        );
+        let client = WsClientBuilder::default()
+            .set_headers(headers)
+            .build(&format!("ws://{}", gateway.addr))
+            .await?;
+        for _ in 0..2 {
+            client
+                .request::<serde_json::Value, Vec<()>>("rpc_methods", vec![])
+                .await?;
+        }
+        let result = client
+            .request::<serde_json::Value, Vec<()>>("rpc_methods", vec![])
+            .await;
+        assert!(result.is_err());
+
+        let batch = serde_json::json!([
+            { "jsonrpc": "2.0", "id": 0, "method": "rpc_methods" },
+            { "jsonrpc": "2.0", "id": 1, "method": "rpc_methods" },
+            { "jsonrpc": "2.0", "id": 2, "method": "rpc_methods" },
+        ]);
+        let request = hyper::Request::post(format!("http://{}", gateway.addr))
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .header(hyper::header::AUTHORIZATION, "Bearer http-secret")
+            .body(hyper::Body::from(batch.to_string()))?;
+        let response = hyper::Client::new().request(request).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let responses: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+        let rejected = responses
+            .iter()
+            .filter(|response| response["error"]["code"] == auth::QUOTA_EXCEEDED_CODE)
+            .count();
+        assert_eq!(rejected, 1);
+
+        let response = hyper::Client::new()
+            .get(format!("http://{}/metrics", gateway.addr).parse()?)
+            .await?;
+        assert_eq!(response.status(), hyper::StatusCode::UNAUTHORIZED);
+
+        gateway.shutdown().await?;
+        Ok(())
+    }
+
+    /// Body of a call to `method`, with the headers signing it by `operator`
+    async fn signed_call(
+        operator: &LocalWallet,
+        nonce: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<(String, Vec<(&'static str, String)>)> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs()
+            .to_string();
+        let signed = auth::SignedRequest {
+            timestamp: &timestamp,
+            nonce,
+            method: "POST",
+            path: "/",
+            body: body.as_bytes(),
+        };
+        let signature = operator.sign_message(signed.message()).await?;
+        let headers = vec![
+            (auth::TIMESTAMP_HEADER, timestamp),
+            (auth::NONCE_HEADER, nonce.to_string()),
+            (auth::SIGNATURE_HEADER, signature.to_string()),
+        ];
+        Ok((body, headers))
+    }
+
+    /// Calls `method` on the admin server at `addr`, signed by `operator`
+    async fn admin_call(
+        addr: SocketAddr,
+        operator: &LocalWallet,
+        nonce: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let (body, headers) = signed_call(operator, nonce, method, params).await?;
+        let mut request = hyper::Request::post(format!("http://{addr}"))
+            .header(hyper::header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let request = request.body(hyper::Body::from(body))?;
+        let response = hyper::Client::new().request(request).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    #[tokio::test]
+    async fn test_admin_controls() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let operator = LocalWallet::new(&mut rand::thread_rng());
+        let key = |name: &str| auth::ApiKey {
+            name: name.to_string(),
+            key: format!("{name}-secret"),
+            quota: None,
+        };
+        let policy = AuthPolicy {
+            api_keys: vec![key("initial")],
+            operators: vec![operator.address()],
+            ..Default::default()
+        };
+        let auth_file =
+            std::env::temp_dir().join(format!("xps-admin-auth-{}.json", std::process::id()));
+        std::fs::write(&auth_file, serde_json::to_string(&policy)?)?;
+        let options = ServerOptions {
+            auth: Some(policy.clone()),
+            auth_file: Some(auth_file.clone()),
+            admin: Some(AdminOptions::default()),
+            ..Default::default()
+        };
+        let gateway = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await?;
+        let admin_addr = gateway.admin_addr.unwrap();
+
+        // pausing rejects the methods changing state on the public server
+        let response = admin_call(admin_addr, &operator, "0", "admin_pause", json!([])).await?;
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": 0, "result": null })
+        );
+        let response = admin_call(admin_addr, &operator, "1", "admin_status", json!([])).await?;
+        assert_eq!(response["result"]["paused"], true);
+        let request = hyper::Request::post(format!("http://{}", gateway.addr))
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .header(hyper::header::AUTHORIZATION, "Bearer initial-secret")
+            .body(hyper::Body::from(
+                json!({ "jsonrpc": "2.0", "id": 0, "method": "xps_v2_sendMessage", "params": [] })
+                    .to_string(),
+            ))?;
+        let response = hyper::Client::new().request(request).await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(
+            response["error"]["code"],
+            jsonrpsee::types::error::SERVER_IS_BUSY_CODE
+        );
+        let response = admin_call(admin_addr, &operator, "2", "admin_resume", json!([])).await?;
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": 0, "result": null })
+        );
+        let response = admin_call(admin_addr, &operator, "3", "admin_status", json!([])).await?;
+        assert_eq!(response["result"]["paused"], false);
+
+        // reloading picks up the API keys added to the policy file
+        let reloaded = AuthPolicy {
+            api_keys: vec![key("added")],
+            ..policy
+        };
+        std::fs::write(&auth_file, serde_json::to_string(&reloaded)?)?;
+        let response = admin_call(
+            admin_addr,
+            &operator,
+            "4",
+            "admin_reloadPolicies",
+            json!([]),
+        )
+        .await?;
+        assert_eq!(response["result"], json!({ "auth": true, "tls": false }));
+        let response =
+            admin_call(admin_addr, &operator, "5", "admin_quotaUsage", json!([])).await?;
+        assert_eq!(response["result"][0]["name"], "added");
+
+        // bumping replaces a pending transaction with one paying higher fees, rounded up
+        let pending = gateway.client.pending().clone();
+        let transaction = |nonce: u64| -> TypedTransaction {
+            TransactionRequest::pay(Address::from_low_u64_be(1), 1)
+                .from(gateway.signer)
+                .nonce(nonce)
+                .gas(21_000)
+                .gas_price(101)
+                .chain_id(1)
+                .into()
+        };
+        let sent = H256::repeat_byte(1);
+        pending.insert(sent, transaction(1));
+        let bumped = H256::repeat_byte(2);
+        mock.push(bumped).unwrap();
+        let response = admin_call(
+            admin_addr,
+            &operator,
+            "6",
+            "admin_bumpTransaction",
+            json!({ "hash": sent, "percent": 50 }),
+        )
+        .await?;
+        assert_eq!(response["result"], json!(bumped));
+        let list = pending.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].hash, bumped);
+        assert_eq!(list[0].transaction.gas_price(), Some(U256::from(152)));
+        assert_eq!(list[0].transaction.nonce(), Some(&U256::from(1)));
+
+        let response = admin_call(
+            admin_addr,
+            &operator,
+            "7",
+            "admin_bumpTransaction",
+            json!({ "hash": bumped, "percent": 1000 }),
+        )
+        .await?;
+        assert_eq!(
+            response["error"]["code"],
+            jsonrpsee::types::error::INVALID_PARAMS_CODE
+        );
+
+        // cancelling replaces it with an empty transfer to the gateway wallet
+        let cancelled = H256::repeat_byte(3);
+        mock.push(cancelled).unwrap();
+        let response = admin_call(
+            admin_addr,
+            &operator,
+            "8",
+            "admin_cancelTransaction",
+            json!({ "hash": bumped }),
+        )
+        .await?;
+        assert_eq!(response["result"], json!(cancelled));
+        let list = pending.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].hash, cancelled);
+        let replacement = &list[0].transaction;
+        assert_eq!(replacement.to_addr(), Some(&gateway.signer));
+        assert_eq!(replacement.value(), Some(&U256::zero()));
+        assert_eq!(replacement.nonce(), Some(&U256::from(1)));
+        assert_eq!(replacement.gas_price(), Some(U256::from(183)));
+
+        pending.remove(&cancelled);
+        gateway.shutdown().await?;
+        std::fs::remove_file(auth_file)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_loopback_without_tls() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let options = ServerOptions {
+            auth: Some(AuthPolicy {
+                operators: vec![Address::from_low_u64_be(1)],
+                ..Default::default()
+            }),
+            admin: Some(AdminOptions {
+                host: "0.0.0.0".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let result = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_client_certificate() -> Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let operator = LocalWallet::new(&mut rand::thread_rng());
+        let options = ServerOptions {
+            auth: Some(AuthPolicy {
+                operators: vec![operator.address()],
+                ..Default::default()
+            }),
+            admin: Some(AdminOptions::default()),
+            tls: Some(tls::testing::test_ca_options()),
+            ..Default::default()
+        };
+        let gateway = start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            options,
+        )
+        .await?;
+        let admin_addr = gateway.admin_addr.unwrap();
+
+        // a signed operator call over TLS, with or without the test client certificate
+        let call = |nonce: &'static str, client_cert: bool| {
+            let operator = operator.clone();
+            async move {
+                let (body, headers) =
+                    signed_call(&operator, nonce, "admin_status", json!([])).await?;
+                let mut request = format!(
+                    "POST / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                     Content-Type: application/json\r\nContent-Length: {}\r\n",
+                    body.len()
+                );
+                for (name, value) in headers {
+                    request.push_str(&format!("{name}: {value}\r\n"));
+                }
+                request.push_str("\r\n");
+                request.push_str(&body);
+                Ok::<_, anyhow::Error>(tls::testing::send(admin_addr, client_cert, &request).await)
+            }
+        };
+
+        let response = call("0", false).await?;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+        assert!(response.contains("Client certificate required"));
+
+        let response = call("1", true).await?;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(r#""paused":false"#), "{response}");
+
+        gateway.shutdown().await?;
+        Ok(())
+    }
+
     #[test]
     fn test_build_api() {
         let methods = RpcModule::new(());
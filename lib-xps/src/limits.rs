@@ -0,0 +1,74 @@
+//! Limits protecting the gateway from oversized or too many requests.
+
+use hyper::{body::HttpBody, Body};
+use thiserror::Error;
+
+/// Default maximum size of a request body, in bytes
+pub const DEFAULT_MAX_REQUEST_BODY_SIZE: u32 = 1024 * 1024;
+/// Default maximum size of a response body, in bytes
+pub const DEFAULT_MAX_RESPONSE_BODY_SIZE: u32 = 10 * 1024 * 1024;
+/// Default maximum number of concurrent connections
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 100;
+/// Default maximum number of subscriptions of a single connection
+pub const DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION: u32 = 16;
+
+/// Limits applied by the JSON-RPC server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of a request body, in bytes. Larger requests are rejected.
+    pub max_request_body_size: u32,
+    /// Maximum size of a response body, in bytes. Larger responses are replaced by an error.
+    pub max_response_body_size: u32,
+    /// Maximum number of concurrent connections. Further connections are refused.
+    pub max_connections: u32,
+    /// Maximum number of subscriptions of a single connection
+    pub max_subscriptions_per_connection: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            max_response_body_size: DEFAULT_MAX_RESPONSE_BODY_SIZE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_subscriptions_per_connection: DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION,
+        }
+    }
+}
+
+/// Errors reading a request body
+#[derive(Debug, Error)]
+pub enum BodyError {
+    #[error("Request body larger than {0} bytes")]
+    TooLarge(u32),
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+}
+
+/// Reads `body` into memory, failing as soon as it exceeds `limit` bytes
+pub async fn read_body(mut body: Body, limit: u32) -> Result<Vec<u8>, BodyError> {
+    let limit_bytes = usize::try_from(limit).unwrap_or(usize::MAX);
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit_bytes {
+            return Err(BodyError::TooLarge(limit));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_body() {
+        let body = read_body(Body::from("0123456789"), 10).await.unwrap();
+        assert_eq!(body, b"0123456789");
+
+        let result = read_body(Body::from("0123456789"), 9).await;
+        assert!(matches!(result, Err(BodyError::TooLarge(9))));
+    }
+}
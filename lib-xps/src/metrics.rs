@@ -0,0 +1,152 @@
+//! Process-wide counters, exposed in the Prometheus text format on `GET /metrics`.
+//!
+//! The counters name the clients of the gateway, so [`MetricsLayer`] is served behind
+//! [`AuthLayer`](crate::auth::AuthLayer), which lets only operators read them when an auth policy
+//! is set. They are served on the admin port as well, when enabled.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    sync::{Mutex, OnceLock},
+    task::{Context, Poll},
+};
+
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    Body, Method, Request, Response,
+};
+use tower::{Layer, Service};
+
+/// Path the metrics are served on
+pub const METRICS_PATH: &str = "/metrics";
+
+/// A counter name along with its sorted `(label, value)` pairs
+type Key = (&'static str, Vec<(&'static str, String)>);
+
+fn counters() -> &'static Mutex<BTreeMap<Key, u64>> {
+    static COUNTERS: OnceLock<Mutex<BTreeMap<Key, u64>>> = OnceLock::new();
+    COUNTERS.get_or_init(Default::default)
+}
+
+/// Increments the counter `name` with `labels`, returning its new value
+pub fn increment(name: &'static str, labels: &[(&'static str, &str)]) -> u64 {
+    let mut counters = counters().lock().unwrap_or_else(|e| e.into_inner());
+    let count = counters.entry(key(name, labels)).or_default();
+    *count += 1;
+    *count
+}
+
+/// Current value of the counter `name` with `labels`
+pub fn get(name: &'static str, labels: &[(&'static str, &str)]) -> u64 {
+    let counters = counters().lock().unwrap_or_else(|e| e.into_inner());
+    counters
+        .get(&key(name, labels))
+        .copied()
+        .unwrap_or_default()
+}
+
+fn key(name: &'static str, labels: &[(&'static str, &str)]) -> Key {
+    let mut labels = labels
+        .iter()
+        .map(|(label, value)| (*label, value.to_string()))
+        .collect::<Vec<_>>();
+    labels.sort();
+    (name, labels)
+}
+
+/// Renders every counter in the Prometheus text exposition format
+pub fn render() -> String {
+    let counters = counters().lock().unwrap_or_else(|e| e.into_inner());
+    let mut output = String::new();
+    let mut previous = None;
+    for ((name, labels), count) in counters.iter() {
+        if previous != Some(name) {
+            output.push_str(&format!("# TYPE {name} counter\n"));
+            previous = Some(name);
+        }
+        let labels = labels
+            .iter()
+            .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            output.push_str(&format!("{name} {count}\n"));
+        } else {
+            output.push_str(&format!("{name}{{{}}} {count}\n", labels.join(",")));
+        }
+    }
+    output
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Layer answering `GET /metrics` in front of the JSON-RPC server, to be placed behind
+/// authentication
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsLayer;
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = Metrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Metrics { inner }
+    }
+}
+
+/// Service serving the metrics, see [`MetricsLayer`]
+#[derive(Debug, Clone)]
+pub struct Metrics<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for Metrics<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync + 'static>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        if request.method() == Method::GET && request.uri().path() == METRICS_PATH {
+            let mut response = Response::new(Body::from(render()));
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            return Box::pin(async { Ok(response) });
+        }
+        let response = self.inner.call(request);
+        Box::pin(async move { response.await.map_err(Into::into) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_increment_and_render() {
+        assert_eq!(increment("xps_test_total", &[("method", "a")]), 1);
+        assert_eq!(increment("xps_test_total", &[("method", "a")]), 2);
+        increment("xps_test_total", &[("method", "b\"")]);
+        assert_eq!(get("xps_test_total", &[("method", "a")]), 2);
+        assert_eq!(get("xps_test_total", &[("method", "c")]), 0);
+
+        let rendered = render();
+        assert!(rendered.contains("# TYPE xps_test_total counter\n"));
+        assert!(rendered.contains("xps_test_total{method=\"a\"} 2\n"));
+        assert!(rendered.contains("xps_test_total{method=\"b\\\"\"} 1\n"));
+    }
+}
@@ -0,0 +1,583 @@
+//! [OpenRPC](https://spec.open-rpc.org) description of the XPS JSON-RPC interface, served by
+//! `rpc.discover`.
+//!
+//! [`document`] describes the `xps` and `xps_v2` namespaces, and [`admin_document`] the `admin`
+//! namespace, served by `rpc.discover` on the admin port. The schemas are derived with
+//! [`schemars`] from the parameter and result types, most of which live in `xps-types`, so they
+//! follow their serde representation. The methods are listed along with their parameters and
+//! result, which are checked at compile time against the signatures of the
+//! [`XpsServer`](crate::XpsServer), [`XpsV2Server`](crate::rpc::XpsV2Server) and
+//! [`AdminServer`](crate::rpc::admin::AdminServer) traits.
+
+use std::future::Future;
+
+use ethers::types::{Address, Bytes, Signature, H256, U256};
+use jsonrpsee::types::{error::INVALID_PARAMS_CODE, ErrorObjectOwned};
+use lib_didethresolver::types::{DidResolutionResult, XmtpAttribute};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    JsonSchema,
+};
+use serde_json::{json, Value};
+use xps_types::{
+    schema, BatchInstallationResult, ChangeOwnerResult, DelegateResult, DelegateType,
+    ExpiringInstallationsResult, GrantInstallationResult, InstallationOperation, KeyPackageResult,
+    Message, OperationResult, PreparedDigest, ResolveDidOptions, SendMessageResult, WalletBalance,
+};
+
+use crate::{
+    auth::KeyUsage,
+    pending::SentTransaction,
+    rpc::{
+        admin::{AdminServer, AdminStatus, ReloadedPolicies},
+        XpsV2Server, GATEWAY_ERROR_CODE,
+    },
+    XpsServer,
+};
+
+/// Version of the OpenRPC specification the document follows
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Summaries of the methods served under the same name by the `xps` and `xps_v2` namespaces
+const XPS_SUMMARIES: &[(&str, &str)] = &[
+    (
+        "sendMessage",
+        "Relay a message signed by its sender to a conversation",
+    ),
+    ("grantInstallation", "Grant an installation to a DID"),
+    ("revokeInstallation", "Revoke an installation from a DID"),
+    (
+        "renewInstallation",
+        "Extend the validity of an installation already granted to a DID",
+    ),
+    (
+        "listExpiringInstallations",
+        "List the installations of a DID expiring within a number of seconds",
+    ),
+    (
+        "batchInstallations",
+        "Grant and revoke several installations of a DID at once",
+    ),
+    ("addDelegate", "Add a delegate to a DID"),
+    ("revokeDelegate", "Revoke a delegate from a DID"),
+    ("changeOwner", "Transfer ownership of a DID"),
+    ("deactivateDid", "Permanently deactivate a DID"),
+    (
+        "fetchKeyPackages",
+        "Fetch the key packages of every installation of a DID",
+    ),
+    (
+        "resolveDid",
+        "Resolve the DID document of a DID, optionally at a past version",
+    ),
+    ("status", "Status of the gateway"),
+    (
+        "walletAddress",
+        "Address of the wallet paying for relayed transactions",
+    ),
+    (
+        "balance",
+        "Balance of the wallet paying for relayed transactions",
+    ),
+    ("nonce", "Registry nonce of the owner of a DID"),
+    ("messageNonce", "Conversation contract nonce of an identity"),
+    ("prepareSendMessage", "Digest to sign for sendMessage"),
+    (
+        "prepareGrantInstallation",
+        "Digest to sign for grantInstallation",
+    ),
+    (
+        "prepareRevokeInstallation",
+        "Digest to sign for revokeInstallation",
+    ),
+];
+
+/// Summaries of the methods of the `admin` namespace
+const ADMIN_SUMMARIES: &[(&str, &str)] = &[
+    (
+        "pause",
+        "Reject the methods changing state until admin_resume is called",
+    ),
+    ("resume", "Serve the methods changing state again"),
+    (
+        "status",
+        "Whether the gateway is paused, and how many of its transactions are pending",
+    ),
+    (
+        "pendingTransactions",
+        "List the transactions broadcast by the gateway which are not mined yet",
+    ),
+    (
+        "cancelTransaction",
+        "Replace a pending transaction with an empty transfer to the gateway wallet",
+    ),
+    (
+        "bumpTransaction",
+        "Broadcast a pending transaction again with higher fees",
+    ),
+    (
+        "reloadPolicies",
+        "Read the auth policy and the TLS certificates from their files again",
+    ),
+    (
+        "quotaUsage",
+        "Calls made with each API key within its current quota window",
+    ),
+    (
+        "withdraw",
+        "Send an amount of wei from the gateway wallet to the treasury",
+    ),
+    (
+        "sweep",
+        "Send the whole balance of the gateway wallet, minus fees, to the treasury, while paused with no transaction pending",
+    ),
+];
+
+/// A method of a namespace, with the schemas of its parameters and result
+struct Method {
+    name: &'static str,
+    params: Vec<Value>,
+    result: Value,
+}
+
+/// The schema of a parameter or result type, or of the type standing in for it after `as`
+macro_rules! schema_of {
+    ($type:ty as $schema:ty) => {
+        $schema
+    };
+    ($type:ty) => {
+        $type
+    };
+}
+
+/// Describes methods of the `#[rpc]` server trait `$server` with `$generator`.
+///
+/// Each method is given as `name => function(param: Type, ...) -> Result`. Types without a
+/// schema of their own are followed by `as` and the type from [`xps_types::schema`] describing
+/// them. A method missing from the trait, or whose parameters or result differ from the trait,
+/// fails to compile.
+macro_rules! methods {
+    ($generator:ident, $server:path, {
+        $($name:literal => $function:ident(
+            $($param:ident: $type:ty $(as $param_schema:ty)?),*
+        ) -> $result:ty $(as $result_schema:ty)?;)*
+    }) => {{
+        #[allow(dead_code, clippy::too_many_arguments)]
+        fn check<S: $server>(server: &S) {
+            $(let _ = |$($param: $type),*| returns::<$result, _>(server.$function($($param),*));)*
+        }
+        vec![$(Method {
+            name: $name,
+            params: vec![$(param::<schema_of!($type $(as $param_schema)?)>(
+                &mut $generator,
+                stringify!($param),
+                stringify!($type),
+            )),*],
+            result: serde_json::to_value(
+                $generator.subschema_for::<schema_of!($result $(as $result_schema)?)>(),
+            )
+            .expect("schemas serialize"),
+        }),*]
+    }};
+}
+
+/// Ties the future returned by a method of a server trait to the result type of the method
+fn returns<T, F: Future<Output = Result<T, ErrorObjectOwned>>>(_: F) {}
+
+/// The OpenRPC document describing every method of the `xps` and `xps_v2` namespaces
+pub fn document() -> Value {
+    let mut generator = generator();
+    let v1 = methods!(generator, XpsServer, {
+        "sendMessage" => send_message(message: Message) -> SendMessageResult;
+        "grantInstallation" => grant_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> GrantInstallationResult;
+        "revokeInstallation" => revoke_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> ();
+        "renewInstallation" => renew_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> GrantInstallationResult;
+        "listExpiringInstallations" => list_expiring_installations(
+            did: String as schema::Did,
+            within_seconds: u64
+        ) -> ExpiringInstallationsResult;
+        "batchInstallations" => batch_installations(
+            did: String as schema::Did,
+            operations: Vec<InstallationOperation>
+        ) -> BatchInstallationResult;
+        "addDelegate" => add_delegate(
+            did: String as schema::Did,
+            delegate_type: DelegateType,
+            delegate: Address as schema::Address,
+            validity: u64,
+            signature: Signature as schema::Signature
+        ) -> DelegateResult;
+        "revokeDelegate" => revoke_delegate(
+            did: String as schema::Did,
+            delegate_type: DelegateType,
+            delegate: Address as schema::Address,
+            signature: Signature as schema::Signature
+        ) -> DelegateResult;
+        "changeOwner" => change_owner(
+            did: String as schema::Did,
+            new_owner: Address as schema::Address,
+            signature: Signature as schema::Signature
+        ) -> ChangeOwnerResult;
+        "deactivateDid" => deactivate_did(
+            did: String as schema::Did,
+            signature: Signature as schema::Signature,
+            confirm: bool
+        ) -> ChangeOwnerResult;
+        "fetchKeyPackages" => fetch_key_packages(did: String as schema::Did) -> KeyPackageResult;
+        "resolveDid" => resolve_did(
+            did: String as schema::Did,
+            options: Option<ResolveDidOptions>
+        ) -> DidResolutionResult as schema::DidResolutionResult;
+        "status" => status() -> String;
+        "walletAddress" => wallet_address() -> Address as schema::Address;
+        "balance" => balance() -> WalletBalance;
+        "nonce" => nonce(did: String as schema::Did) -> U256 as schema::Uint256;
+        "messageNonce" => message_nonce(
+            identity: Address as schema::Address
+        ) -> U256 as schema::Uint256;
+        "prepareSendMessage" => prepare_send_message(
+            conversation_id: [u8; 32],
+            payload: Bytes as schema::HexBytes,
+            identity: Address as schema::Address
+        ) -> PreparedDigest;
+        "prepareGrantInstallation" => prepare_grant_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>
+        ) -> PreparedDigest;
+        "prepareRevokeInstallation" => prepare_revoke_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>
+        ) -> PreparedDigest;
+    });
+    let v2 = methods!(generator, XpsV2Server, {
+        "sendMessage" => send_message(message: Message) -> OperationResult;
+        "grantInstallation" => grant_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "revokeInstallation" => revoke_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "renewInstallation" => renew_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "listExpiringInstallations" => list_expiring_installations(
+            did: String as schema::Did,
+            within_seconds: u64
+        ) -> ExpiringInstallationsResult;
+        "batchInstallations" => batch_installations(
+            did: String as schema::Did,
+            operations: Vec<InstallationOperation>
+        ) -> OperationResult;
+        "addDelegate" => add_delegate(
+            did: String as schema::Did,
+            delegate_type: DelegateType,
+            delegate: Address as schema::Address,
+            validity: u64,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "revokeDelegate" => revoke_delegate(
+            did: String as schema::Did,
+            delegate_type: DelegateType,
+            delegate: Address as schema::Address,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "changeOwner" => change_owner(
+            did: String as schema::Did,
+            new_owner: Address as schema::Address,
+            signature: Signature as schema::Signature
+        ) -> OperationResult;
+        "deactivateDid" => deactivate_did(
+            did: String as schema::Did,
+            signature: Signature as schema::Signature,
+            confirm: bool
+        ) -> OperationResult;
+        "fetchKeyPackages" => fetch_key_packages(did: String as schema::Did) -> KeyPackageResult;
+        "resolveDid" => resolve_did(
+            did: String as schema::Did,
+            options: Option<ResolveDidOptions>
+        ) -> DidResolutionResult as schema::DidResolutionResult;
+        "status" => status() -> String;
+        "walletAddress" => wallet_address() -> Address as schema::Address;
+        "balance" => balance() -> WalletBalance;
+        "nonce" => nonce(did: String as schema::Did) -> U256 as schema::Uint256;
+        "messageNonce" => message_nonce(
+            identity: Address as schema::Address
+        ) -> U256 as schema::Uint256;
+        "prepareSendMessage" => prepare_send_message(
+            conversation_id: [u8; 32],
+            payload: Bytes as schema::HexBytes,
+            identity: Address as schema::Address
+        ) -> PreparedDigest;
+        "prepareGrantInstallation" => prepare_grant_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>
+        ) -> PreparedDigest;
+        "prepareRevokeInstallation" => prepare_revoke_installation(
+            did: String as schema::Did,
+            name: XmtpAttribute as schema::XmtpAttribute,
+            value: Vec<u8>
+        ) -> PreparedDigest;
+    });
+
+    let v1 = v1.into_iter().map(|method| {
+        let summary = format!(
+            "{} (deprecated, use xps_v2_{})",
+            summary(XPS_SUMMARIES, method.name),
+            method.name
+        );
+        describe("xps", method, &summary)
+    });
+    let v2 = v2.into_iter().map(|method| {
+        let summary = summary(XPS_SUMMARIES, method.name);
+        describe("xps_v2", method, summary)
+    });
+    build("XMTP Postal Service", v1.chain(v2).collect(), generator)
+}
+
+/// The OpenRPC document describing every method of the `admin` namespace
+pub fn admin_document() -> Value {
+    let mut generator = generator();
+    let methods = methods!(generator, AdminServer, {
+        "pause" => pause() -> ();
+        "resume" => resume() -> ();
+        "status" => status() -> AdminStatus;
+        "pendingTransactions" => pending_transactions() -> Vec<SentTransaction>;
+        "cancelTransaction" => cancel_transaction(
+            hash: H256 as schema::Hash,
+            percent: Option<u64>
+        ) -> H256 as schema::Hash;
+        "bumpTransaction" => bump_transaction(
+            hash: H256 as schema::Hash,
+            percent: Option<u64>
+        ) -> H256 as schema::Hash;
+        "reloadPolicies" => reload_policies() -> ReloadedPolicies;
+        "quotaUsage" => quota_usage() -> Vec<KeyUsage>;
+        "withdraw" => withdraw(amount: U256 as schema::Uint256, confirm: bool) -> OperationResult;
+        "sweep" => sweep(confirm: bool) -> OperationResult;
+    });
+    let methods = methods
+        .into_iter()
+        .map(|method| {
+            let summary = summary(ADMIN_SUMMARIES, method.name);
+            describe("admin", method, summary)
+        })
+        .collect();
+    build("XMTP Postal Service administration", methods, generator)
+}
+
+/// Generator of the schemas of the document, referenced from `#/components/schemas`
+fn generator() -> SchemaGenerator {
+    SchemaSettings::draft07()
+        .with(|settings| settings.definitions_path = "#/components/schemas/".to_string())
+        .into_generator()
+}
+
+fn build(title: &str, methods: Vec<Value>, mut generator: SchemaGenerator) -> Value {
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": title,
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+        "components": {
+            "schemas": generator.take_definitions(),
+            "errors": {
+                "GatewayError": {
+                    "code": GATEWAY_ERROR_CODE,
+                    "message": "The gateway failed to process the request, for instance because a contract call reverted or the DID is deactivated",
+                },
+                "InvalidParams": {
+                    "code": INVALID_PARAMS_CODE,
+                    "message": "The parameters are invalid, or an irreversible operation was not confirmed",
+                },
+            },
+        },
+    })
+}
+
+fn summary<'a>(summaries: &[(&str, &'a str)], name: &str) -> &'a str {
+    summaries
+        .iter()
+        .find_map(|(method, summary)| (*method == name).then_some(*summary))
+        .unwrap_or_else(|| panic!("{name} has no summary"))
+}
+
+fn describe(namespace: &str, method: Method, summary: &str) -> Value {
+    json!({
+        "name": format!("{namespace}_{}", method.name),
+        "summary": summary,
+        "paramStructure": "either",
+        "params": method.params,
+        "result": { "name": "result", "schema": method.result },
+        "errors": [
+            { "$ref": "#/components/errors/GatewayError" },
+            { "$ref": "#/components/errors/InvalidParams" },
+        ],
+    })
+}
+
+/// A parameter of type `rust_type`, which may be omitted if it is an `Option`
+fn param<T: JsonSchema>(generator: &mut SchemaGenerator, name: &str, rust_type: &str) -> Value {
+    json!({
+        "name": name,
+        "required": !rust_type.starts_with("Option"),
+        "schema": generator.subschema_for::<T>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::GatewayContext, XpsMethods};
+    use ethers::{providers::Provider, types::U64};
+
+    fn find<'a>(document: &'a Value, name: &str) -> &'a Value {
+        document["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|method| method["name"] == name)
+            .unwrap_or_else(|| panic!("{name} is not described"))
+    }
+
+    #[test]
+    fn test_optional_params() {
+        let document = document();
+        let params = &find(&document, "xps_v2_resolveDid")["params"];
+        assert_eq!(params[0]["name"], "did");
+        assert_eq!(params[0]["required"], true);
+        assert_eq!(params[1]["name"], "options");
+        assert_eq!(params[1]["required"], false);
+
+        let document = admin_document();
+        let params = &find(&document, "admin_bumpTransaction")["params"];
+        assert_eq!(params[0]["required"], true);
+        assert_eq!(params[1]["name"], "percent");
+        assert_eq!(params[1]["required"], false);
+    }
+
+    #[test]
+    fn test_foreign_types_described() {
+        let document = document();
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(schemas["Hash"]["pattern"], "^0x[0-9a-fA-F]{64}$");
+        assert_eq!(
+            schemas["Signature"]["properties"]["r"]["$ref"],
+            "#/components/schemas/Uint256"
+        );
+        assert_eq!(
+            schemas["Message"]["properties"]["payload"]["$ref"],
+            "#/components/schemas/HexBytes"
+        );
+        assert!(schemas["OperationResult"]["properties"]["blockNumber"].is_object());
+
+        let method = find(&document, "xps_v2_nonce");
+        assert_eq!(
+            method["params"][0]["schema"]["$ref"],
+            "#/components/schemas/Did"
+        );
+        assert_eq!(
+            method["result"]["schema"]["$ref"],
+            "#/components/schemas/Uint256"
+        );
+        let method = find(&document, "xps_revokeInstallation");
+        assert_eq!(method["result"]["schema"]["type"], "null");
+    }
+
+    #[test]
+    fn test_deprecated_summaries() {
+        let document = document();
+        assert_eq!(
+            find(&document, "xps_changeOwner")["summary"],
+            "Transfer ownership of a DID (deprecated, use xps_v2_changeOwner)"
+        );
+        assert_eq!(
+            find(&document, "xps_v2_changeOwner")["summary"],
+            "Transfer ownership of a DID"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_method_described() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(U64::from(1)).unwrap();
+        let context = GatewayContext::new(Address::zero(), Address::zero(), provider)
+            .await
+            .unwrap();
+        let mut registered = XpsServer::into_rpc(XpsMethods::new(&context))
+            .method_names()
+            .chain(XpsV2Server::into_rpc(XpsMethods::new(&context)).method_names())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        registered.sort();
+
+        let mut described = document()["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|method| method["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        described.sort();
+
+        assert_eq!(described, registered);
+    }
+
+    #[test]
+    fn test_references_resolve() {
+        for document in [document(), admin_document()] {
+            let schemas = document["components"]["schemas"].as_object().unwrap();
+
+            fn collect(value: &Value, references: &mut Vec<String>) {
+                match value {
+                    Value::Object(map) => {
+                        if let Some(Value::String(reference)) = map.get("$ref") {
+                            references.push(reference.clone());
+                        }
+                        map.values().for_each(|v| collect(v, references));
+                    }
+                    Value::Array(values) => values.iter().for_each(|v| collect(v, references)),
+                    _ => (),
+                }
+            }
+            let mut references = Vec::new();
+            collect(&document, &mut references);
+
+            for reference in references {
+                if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+                    assert!(schemas.contains_key(name), "unresolved schema {name}");
+                } else {
+                    let name = reference.strip_prefix("#/components/errors/").unwrap();
+                    assert!(document["components"]["errors"].get(name).is_some());
+                }
+            }
+        }
+    }
+}
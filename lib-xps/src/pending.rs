@@ -0,0 +1,335 @@
+//! Transactions broadcast by the gateway wallet and not yet known to be mined.
+//!
+//! [`PendingTracker`] wraps the signing middleware of the gateway and records every transaction
+//! sent from the gateway wallet in a shared [`PendingTransactions`] set. An entry is removed as
+//! soon as a receipt is fetched for it through the tracker, and the set is pruned periodically
+//! in the background, see [`PendingTracker::prune_every`], since the receipts awaited by contract
+//! calls are polled from the provider directly. Whatever remains when the gateway shuts down can
+//! be persisted and followed up on, by a gateway signing with the same wallet, see
+//! [`ServerOptions::wallet_key_file`](crate::ServerOptions::wallet_key_file).
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, MiddlewareError, PendingTransaction},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockId, TransactionReceipt, TxHash, H256,
+    },
+};
+use futures::{stream, StreamExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// How often the pending transactions are checked for receipts in the background, by default
+pub const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Maximum number of receipts fetched at once while pruning
+const PRUNE_CONCURRENCY: usize = 8;
+
+/// A transaction broadcast by the gateway wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SentTransaction {
+    #[schemars(with = "xps_types::schema::Hash")]
+    pub hash: H256,
+    /// The transaction as it was signed, including its nonce and gas price
+    #[schemars(with = "serde_json::Map<String, serde_json::Value>")]
+    pub transaction: TypedTransaction,
+    /// Unix timestamp (in seconds) of the broadcast
+    #[serde(rename = "sentAt")]
+    pub sent_at: u64,
+}
+
+/// The set of transactions broadcast by the gateway wallet and not known to be mined
+#[derive(Debug, Default)]
+pub struct PendingTransactions {
+    sent: Mutex<BTreeMap<H256, SentTransaction>>,
+}
+
+impl PendingTransactions {
+    pub fn insert(&self, hash: H256, transaction: TypedTransaction) {
+        let sent_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.lock().insert(
+            hash,
+            SentTransaction {
+                hash,
+                transaction,
+                sent_at,
+            },
+        );
+    }
+
+    pub fn remove(&self, hash: &H256) -> Option<SentTransaction> {
+        self.lock().remove(hash)
+    }
+
+    /// The pending transactions, ordered by hash
+    pub fn list(&self) -> Vec<SentTransaction> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Removes the transactions which were mined according to `client`, returning the others
+    pub async fn prune<M: Middleware>(&self, client: &M) -> Vec<SentTransaction> {
+        let mut pending = stream::iter(self.list())
+            .map(|sent| async move {
+                match client.get_transaction_receipt(sent.hash).await {
+                    Ok(Some(_)) => {
+                        self.remove(&sent.hash);
+                        None
+                    }
+                    Ok(None) => Some(sent),
+                    Err(e) => {
+                        log::warn!("Failed to fetch the receipt of {:?}: {e}", sent.hash);
+                        Some(sent)
+                    }
+                }
+            })
+            .buffer_unordered(PRUNE_CONCURRENCY)
+            .filter_map(|sent| async move { sent })
+            .collect::<Vec<_>>()
+            .await;
+        pending.sort_by_key(|sent| sent.hash);
+        pending
+    }
+
+    /// Prunes like [`Self::prune`] for up to `timeout`, returning every transaction not known to
+    /// be mined by then
+    pub async fn prune_within<M: Middleware>(
+        &self,
+        client: &M,
+        timeout: Duration,
+    ) -> Vec<SentTransaction> {
+        match tokio::time::timeout(timeout, self.prune(client)).await {
+            Ok(pending) => pending,
+            Err(_) => {
+                log::warn!("Timed out checking the receipts of the pending transactions");
+                self.list()
+            }
+        }
+    }
+
+    /// Writes the pending transactions as JSON to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(&self.list())?)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<H256, SentTransaction>> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Middleware recording the transactions sent from the default sender of `M`
+#[derive(Debug)]
+pub struct PendingTracker<M> {
+    inner: M,
+    pending: Arc<PendingTransactions>,
+}
+
+impl<M> PendingTracker<M> {
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            pending: Arc::new(PendingTransactions::default()),
+        }
+    }
+
+    /// The transactions sent through this middleware and not known to be mined
+    pub fn pending(&self) -> &Arc<PendingTransactions> {
+        &self.pending
+    }
+}
+
+impl<M: Middleware + 'static> PendingTracker<M> {
+    /// Prunes the pending transactions of `tracker` every `period` in the background, until the
+    /// tracker is dropped
+    pub fn prune_every(tracker: &Arc<Self>, period: Duration) {
+        let tracker = Arc::downgrade(tracker);
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + period;
+            let mut interval = tokio::time::interval_at(start, period);
+            loop {
+                interval.tick().await;
+                let Some(tracker) = tracker.upgrade() else {
+                    break;
+                };
+                tracker.pending.prune(tracker.as_ref()).await;
+            }
+        });
+    }
+}
+
+impl<P: Middleware> PendingTracker<SignerMiddleware<P, LocalWallet>> {
+    /// The wallet signing transactions
+    pub fn signer(&self) -> &LocalWallet {
+        self.inner.signer()
+    }
+
+    /// The address of the wallet signing transactions
+    pub fn address(&self) -> Address {
+        self.inner.signer().address()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PendingTrackerError<M: Middleware> {
+    #[error(transparent)]
+    Middleware(M::Error),
+}
+
+impl<M: Middleware> MiddlewareError for PendingTrackerError<M> {
+    type Inner = M::Error;
+
+    fn from_err(e: Self::Inner) -> Self {
+        PendingTrackerError::Middleware(e)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            PendingTrackerError::Middleware(e) => Some(e),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Middleware for PendingTracker<M> {
+    type Error = PendingTrackerError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+        self.inner
+            .fill_transaction(&mut tx, block)
+            .await
+            .map_err(MiddlewareError::from_err)?;
+        let pending = self
+            .inner
+            .send_transaction(tx.clone(), block)
+            .await
+            .map_err(MiddlewareError::from_err)?;
+
+        let sender = self.inner.default_sender();
+        if sender.is_some() && tx.from() == sender.as_ref() {
+            self.pending.insert(pending.tx_hash(), tx);
+        }
+        Ok(pending)
+    }
+
+    async fn get_transaction_receipt<T: Send + Sync + Into<TxHash>>(
+        &self,
+        transaction_hash: T,
+    ) -> Result<Option<TransactionReceipt>, Self::Error> {
+        let hash = transaction_hash.into();
+        let receipt = self
+            .inner
+            .get_transaction_receipt(hash)
+            .await
+            .map_err(MiddlewareError::from_err)?;
+        if receipt.is_some() {
+            self.pending.remove(&hash);
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{TransactionRequest, U256},
+    };
+
+    #[tokio::test]
+    async fn test_prune() {
+        let (provider, mock) = Provider::mocked();
+        let pending = PendingTransactions::default();
+        let mined = H256::repeat_byte(1);
+        let stuck = H256::repeat_byte(2);
+        let tx: TypedTransaction = TransactionRequest::new().nonce(U256::from(7)).into();
+        pending.insert(mined, tx.clone());
+        pending.insert(stuck, tx);
+
+        // responses are popped in reverse order, and transactions are checked by hash
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(Some(TransactionReceipt::default())).unwrap();
+
+        let remaining = pending.prune(&provider).await;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].hash, stuck);
+        assert_eq!(pending.list(), remaining);
+        assert_eq!(remaining[0].transaction.nonce(), Some(&U256::from(7)));
+    }
+
+    #[tokio::test]
+    async fn test_receipt_removes_pending() {
+        let (provider, mock) = Provider::mocked();
+        let tracker = PendingTracker::new(provider);
+        let hash = H256::repeat_byte(1);
+        tracker
+            .pending()
+            .insert(hash, TransactionRequest::new().into());
+
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        assert!(tracker
+            .get_transaction_receipt(hash)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(tracker.pending().list().len(), 1);
+
+        mock.push(Some(TransactionReceipt::default())).unwrap();
+        assert!(tracker
+            .get_transaction_receipt(hash)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(tracker.pending().list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prune_within() {
+        let (provider, _mock) = Provider::mocked();
+        let pending = PendingTransactions::default();
+        pending.insert(H256::repeat_byte(1), TransactionRequest::new().into());
+        // the mock has no receipt to answer with, so the transaction is kept
+        let remaining = pending.prune_within(&provider, Duration::ZERO).await;
+        assert_eq!(remaining, pending.list());
+    }
+
+    #[test]
+    fn test_save() -> Result<()> {
+        let pending = PendingTransactions::default();
+        pending.insert(H256::repeat_byte(1), TransactionRequest::new().into());
+
+        let path = std::env::temp_dir().join(format!("xps-pending-{}.json", std::process::id()));
+        pending.save(&path)?;
+        let saved: Vec<SentTransaction> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(saved, pending.list());
+        Ok(())
+    }
+}
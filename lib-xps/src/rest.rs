@@ -0,0 +1,350 @@
+//! REST facade over the JSON-RPC interface.
+//!
+//! Requests to `/rpc/v1/<method>` are rewritten into a JSON-RPC call of `xps_<method>` and
+//! handed to the same [`crate::XpsServer`] implementation serving JSON-RPC. The JSON body of the
+//! request holds the parameters, either as an array or an object of named parameters, and may
+//! be omitted for methods without parameters. The response body holds the `result` of the call,
+//! or its `error` object along with an HTTP status matching the error code.
+//!
+//! Methods changing state, see [`crate::admin::is_mutating`], are only served over `POST`, so
+//! that a link or a cross-site `GET` cannot relay a transaction.
+
+use std::{
+    error::Error as StdError,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use hyper::{
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE},
+    Body, Method, Request, Response, StatusCode, Uri,
+};
+use jsonrpsee::types::error::{
+    INVALID_PARAMS_CODE, INVALID_REQUEST_CODE, METHOD_NOT_FOUND_CODE, OVERSIZED_REQUEST_CODE,
+    PARSE_ERROR_CODE, SERVER_IS_BUSY_CODE,
+};
+use serde_json::{json, Value};
+use tower::{Layer, Service};
+
+use crate::{
+    admin::is_mutating,
+    auth::QUOTA_EXCEEDED_CODE,
+    limits::{read_body, BodyError},
+    rpc::GATEWAY_ERROR_CODE,
+};
+
+/// Prefix of the paths served by the REST facade
+pub const REST_PREFIX: &str = "/rpc/v1/";
+
+/// Documented REST names which differ from the JSON-RPC method name
+const ALIASES: &[(&str, &str)] = &[("sendGroupMessage", "sendMessage")];
+
+type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Layer serving the REST facade in front of the JSON-RPC server
+#[derive(Debug, Clone, Copy)]
+pub struct RestLayer {
+    max_body_size: u32,
+}
+
+impl RestLayer {
+    /// Serves the REST facade, rejecting bodies larger than `max_body_size` bytes
+    pub fn new(max_body_size: u32) -> Self {
+        Self { max_body_size }
+    }
+}
+
+impl<S> Layer<S> for RestLayer {
+    type Service = Rest<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Rest {
+            inner,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Service translating REST requests into JSON-RPC calls, see [`RestLayer`]
+#[derive(Debug, Clone)]
+pub struct Rest<S> {
+    inner: S,
+    max_body_size: u32,
+}
+
+impl<S> Service<Request<Body>> for Rest<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<BoxError> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let Some(method) = rest_method(request.uri().path()) else {
+            let response = self.inner.call(request);
+            return Box::pin(async move { response.await.map_err(Into::into) });
+        };
+
+        let allowed = match *request.method() {
+            Method::POST => true,
+            Method::GET => !is_mutating(&method),
+            _ => false,
+        };
+        if !allowed {
+            return Box::pin(async { Ok(reply(StatusCode::METHOD_NOT_ALLOWED, Value::Null)) });
+        }
+
+        let (parts, body) = request.into_parts();
+        // take the service which was polled ready, leaving a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_body_size = self.max_body_size;
+        Box::pin(async move {
+            let body = match read_body(body, max_body_size).await {
+                Ok(body) => body,
+                Err(e @ BodyError::TooLarge(_)) => {
+                    return Ok(reply(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        error_body(OVERSIZED_REQUEST_CODE, &e.to_string()),
+                    ))
+                }
+                Err(BodyError::Hyper(e)) => return Err(e.into()),
+            };
+            let params = if body.iter().all(u8::is_ascii_whitespace) {
+                None
+            } else {
+                match serde_json::from_slice::<Value>(&body) {
+                    Ok(params @ (Value::Array(_) | Value::Object(_))) => Some(params),
+                    _ => {
+                        return Ok(reply(
+                            StatusCode::BAD_REQUEST,
+                            error_body(
+                                PARSE_ERROR_CODE,
+                                "Body must be a JSON array or object of parameters",
+                            ),
+                        ))
+                    }
+                }
+            };
+
+            let mut call = json!({ "jsonrpc": "2.0", "id": 0, "method": method });
+            if let Some(params) = params {
+                call["params"] = params;
+            }
+
+            let mut rpc_request = Request::from_parts(parts, Body::from(call.to_string()));
+            *rpc_request.method_mut() = Method::POST;
+            *rpc_request.uri_mut() = Uri::from_static("/");
+            let headers = rpc_request.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+            let response = inner.call(rpc_request).await.map_err(Into::into)?;
+            if response.status() != StatusCode::OK {
+                return Ok(response);
+            }
+
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            let mut rpc_response: Value = serde_json::from_slice(&body)?;
+            Ok(match rpc_response.get_mut("error") {
+                Some(error) => {
+                    let code = error["code"].as_i64().unwrap_or_default();
+                    reply(status_for(code), error.take())
+                }
+                None => reply(StatusCode::OK, rpc_response["result"].take()),
+            })
+        })
+    }
+}
+
+/// The JSON-RPC method served at `path`, if it is a REST path
+fn rest_method(path: &str) -> Option<String> {
+    let name = path.strip_prefix(REST_PREFIX)?.trim_end_matches('/');
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, method)| *method);
+    Some(format!("xps_{name}"))
+}
+
+/// HTTP status reported for a JSON-RPC error code
+fn status_for(code: i64) -> StatusCode {
+    match i32::try_from(code).unwrap_or_default() {
+        PARSE_ERROR_CODE | INVALID_REQUEST_CODE | INVALID_PARAMS_CODE => StatusCode::BAD_REQUEST,
+        METHOD_NOT_FOUND_CODE => StatusCode::NOT_FOUND,
+        OVERSIZED_REQUEST_CODE => StatusCode::PAYLOAD_TOO_LARGE,
+        SERVER_IS_BUSY_CODE => StatusCode::SERVICE_UNAVAILABLE,
+        GATEWAY_ERROR_CODE => StatusCode::UNPROCESSABLE_ENTITY,
+        QUOTA_EXCEEDED_CODE => StatusCode::TOO_MANY_REQUESTS,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_body(code: i32, message: &str) -> Value {
+    json!({ "code": code, "message": message })
+}
+
+fn reply(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Contracts, ServerOptions};
+    use ethers::{providers::Provider, types::U64};
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    /// JSON-RPC service answering `xps_status`, failing `xps_nonce` and echoing parameters
+    async fn rpc(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+        assert_eq!(request.uri().path(), "/");
+        let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+        let call: Value = serde_json::from_slice(&body).unwrap();
+        let response = match call["method"].as_str().unwrap() {
+            "xps_status" => json!({ "jsonrpc": "2.0", "id": 0, "result": "OK" }),
+            "xps_nonce" => json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "error": { "code": GATEWAY_ERROR_CODE, "message": "DID deactivated" }
+            }),
+            "xps_sendMessage" => json!({ "jsonrpc": "2.0", "id": 0, "result": call["params"] }),
+            _ => json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "error": { "code": METHOD_NOT_FOUND_CODE, "message": "Method not found" }
+            }),
+        };
+        Ok(Response::new(Body::from(response.to_string())))
+    }
+
+    async fn send(method: Method, path: &str, body: &str) -> (StatusCode, Value) {
+        let service = RestLayer::new(16).layer(service_fn(rpc));
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_result() {
+        let (status, body) = send(Method::GET, "/rpc/v1/status", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!("OK"));
+    }
+
+    #[tokio::test]
+    async fn test_params_and_alias() {
+        let (status, body) = send(Method::POST, "/rpc/v1/sendGroupMessage", "[1, 2]").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let (status, body) = send(Method::POST, "/rpc/v1/nonce", "[\"0x00\"]").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["message"], "DID deactivated");
+
+        let (status, _) = send(Method::POST, "/rpc/v1/unknown", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(Method::POST, "/rpc/v1/status", "\"OK\"").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(Method::DELETE, "/rpc/v1/status", "").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+
+        let (status, _) = send(Method::GET, "/rpc/v1/sendGroupMessage", "").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+
+        let (status, body) = send(Method::POST, "/rpc/v1/sendMessage", "[1, 2, 3, 4, 5, 6]").await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], OVERSIZED_REQUEST_CODE);
+    }
+
+    #[tokio::test]
+    async fn test_server() -> anyhow::Result<()> {
+        let (provider, mock) = Provider::mocked();
+        // chainID
+        mock.push(U64::from(0x1)).unwrap();
+        let gateway = crate::start(
+            "127.0.0.1".to_string(),
+            0,
+            provider,
+            Contracts::default(),
+            ServerOptions::default(),
+        )
+        .await?;
+
+        let client = hyper::Client::new();
+        let request = |method: Method, path: &str| {
+            Request::builder()
+                .method(method)
+                .uri(format!("http://{}{path}", gateway.addr))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = client
+            .request(request(Method::GET, "/rpc/v1/status"))
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        assert_eq!(serde_json::from_slice::<Value>(&body)?, json!("OK"));
+
+        for path in ["/rpc/v1/sendMessage", "/rpc/v1/grantInstallation"] {
+            let response = client.request(request(Method::GET, path)).await?;
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        // the missing parameters are rejected, rather than the method
+        let response = client
+            .request(request(Method::POST, "/rpc/v1/sendMessage"))
+            .await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        gateway.shutdown().await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_rest_method() {
+        assert_eq!(
+            rest_method("/rpc/v1/status"),
+            Some("xps_status".to_string())
+        );
+        assert_eq!(
+            rest_method("/rpc/v1/status/"),
+            Some("xps_status".to_string())
+        );
+        assert_eq!(
+            rest_method("/rpc/v1/sendGroupMessage"),
+            Some("xps_sendMessage".to_string())
+        );
+        assert_eq!(rest_method("/"), None);
+        assert_eq!(rest_method("/rpc/v1/"), None);
+        assert_eq!(rest_method("/rpc/v1/a/b"), None);
+    }
+}
@@ -1,6 +1,7 @@
 //! RPC Interface and Implementations for XPS
-mod api;
+pub mod admin;
 mod methods;
+pub mod v2;
 
-pub use api::*;
 pub use methods::*;
+pub use xps_types::rpc::*;
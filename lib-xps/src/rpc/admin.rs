@@ -0,0 +1,484 @@
+//! The `admin` JSON-RPC namespace, for the operators of the gateway. See [`crate::admin`].
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::{
+    providers::{Middleware, ProviderError},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, H256, U256,
+    },
+};
+use jsonrpsee::{
+    proc_macros::rpc,
+    types::{error::INVALID_PARAMS_CODE, ErrorObjectOwned},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use xps_types::OperationResult;
+
+use super::GATEWAY_ERROR_CODE;
+use crate::{
+    admin::Controls,
+    audit,
+    auth::{AuthPolicy, KeyUsage},
+    pending::SentTransaction,
+    types::{GatewayContext, GatewaySigner},
+};
+
+/// Fee increase of a replacement transaction, in percent, unless specified
+pub const DEFAULT_FEE_BUMP_PERCENT: u64 = 20;
+/// Minimum fee increase for nodes to accept a replacement transaction, in percent
+pub const MIN_FEE_BUMP_PERCENT: u64 = 10;
+/// Maximum fee increase of a replacement transaction, in percent, so a typo cannot drain the
+/// wallet
+pub const MAX_FEE_BUMP_PERCENT: u64 = 100;
+/// Gas of a plain transfer of ether, such as the one replacing a cancelled transaction
+const TRANSFER_GAS: u64 = 21_000;
+
+/// State of the gateway, as returned by `admin_status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AdminStatus {
+    /// Whether the methods changing state are paused
+    pub paused: bool,
+    /// Number of transactions broadcast by the gateway and not known to be mined
+    #[serde(rename = "pendingTransactions")]
+    pub pending_transactions: usize,
+}
+
+/// Which policies `admin_reloadPolicies` read again
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ReloadedPolicies {
+    /// Whether the auth policy was reloaded from its file
+    pub auth: bool,
+    /// Whether the TLS certificates were reloaded
+    pub tls: bool,
+}
+
+/// Admin JSON-RPC Interface Methods
+#[rpc(server, client, namespace = "admin")]
+pub trait Admin {
+    /// Rejects calls to the `xps` and `xps_v2` methods changing state until `admin_resume` is
+    /// called. Calls already in flight complete.
+    #[method(name = "pause")]
+    async fn pause(&self) -> Result<(), ErrorObjectOwned>;
+
+    /// Serves the methods changing state again
+    #[method(name = "resume")]
+    async fn resume(&self) -> Result<(), ErrorObjectOwned>;
+
+    /// Whether the gateway is paused, and how many of its transactions are pending
+    #[method(name = "status")]
+    async fn status(&self) -> Result<AdminStatus, ErrorObjectOwned>;
+
+    /// Lists the transactions broadcast by the gateway which are not mined yet
+    #[method(name = "pendingTransactions")]
+    async fn pending_transactions(&self) -> Result<Vec<SentTransaction>, ErrorObjectOwned>;
+
+    /// Replaces a pending transaction with an empty transfer to the gateway wallet, paying fees
+    /// higher by `percent` (20% by default, between 10% and 100%). Returns the hash of the
+    /// replacement.
+    #[method(name = "cancelTransaction")]
+    async fn cancel_transaction(
+        &self,
+        hash: H256,
+        percent: Option<u64>,
+    ) -> Result<H256, ErrorObjectOwned>;
+
+    /// Broadcasts a pending transaction again, paying fees higher by `percent` (20% by default,
+    /// between 10% and 100%). Returns the hash of the replacement.
+    #[method(name = "bumpTransaction")]
+    async fn bump_transaction(
+        &self,
+        hash: H256,
+        percent: Option<u64>,
+    ) -> Result<H256, ErrorObjectOwned>;
+
+    /// Reads the auth policy and the TLS certificates from their files again
+    #[method(name = "reloadPolicies")]
+    async fn reload_policies(&self) -> Result<ReloadedPolicies, ErrorObjectOwned>;
+
+    /// Calls made with each API key within its current quota window
+    #[method(name = "quotaUsage")]
+    async fn quota_usage(&self) -> Result<Vec<KeyUsage>, ErrorObjectOwned>;
+
+    /// Sends `amount` wei from the gateway wallet to the configured treasury address, as a plain
+    /// transfer. Moving funds out of the gateway must be explicitly confirmed.
+    #[method(name = "withdraw")]
+    async fn withdraw(
+        &self,
+        amount: U256,
+        confirm: bool,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Sends the whole balance of the gateway wallet, minus the fee of the transfer, to the
+    /// configured treasury address. Moving funds out of the gateway must be explicitly confirmed,
+    /// and the gateway must be paused, with no transaction pending, so nothing else spends from
+    /// the wallet meanwhile.
+    #[method(name = "sweep")]
+    async fn sweep(&self, confirm: bool) -> Result<OperationResult, ErrorObjectOwned>;
+}
+
+/// Gateway Methods for the admin namespace
+pub struct AdminMethods<P: Middleware + 'static> {
+    signer: Arc<GatewaySigner<P>>,
+    controls: Controls,
+}
+
+impl<P: Middleware> AdminMethods<P> {
+    pub fn new(context: &GatewayContext<P>, controls: Controls) -> Self {
+        Self {
+            signer: context.signer.clone(),
+            controls,
+        }
+    }
+}
+
+impl<P: Middleware + 'static> AdminMethods<P> {
+    /// Broadcasts a replacement of the pending transaction `hash`, with fees bumped by `percent`
+    async fn replace(
+        &self,
+        hash: H256,
+        percent: Option<u64>,
+        cancel: bool,
+    ) -> Result<H256, AdminError<GatewaySigner<P>>> {
+        let percent = percent.unwrap_or(DEFAULT_FEE_BUMP_PERCENT);
+        if !(MIN_FEE_BUMP_PERCENT..=MAX_FEE_BUMP_PERCENT).contains(&percent) {
+            return Err(AdminError::InvalidBump(percent));
+        }
+        let pending = self.signer.pending();
+        let sent = pending
+            .list()
+            .into_iter()
+            .find(|sent| sent.hash == hash)
+            .ok_or(AdminError::UnknownTransaction(hash))?;
+
+        let mut tx = sent.transaction;
+        if cancel {
+            tx.set_to(self.signer.address());
+            tx.set_value(U256::zero());
+            tx.set_data(Bytes::default());
+            tx.set_gas(TRANSFER_GAS);
+        }
+        bump_fees(&mut tx, percent);
+        let replacement = self
+            .signer
+            .send_transaction(tx, None)
+            .await
+            .map_err(AdminError::Middleware)?
+            .tx_hash();
+        pending.remove(&hash);
+        Ok(replacement)
+    }
+
+    /// Transfers `amount`, or the whole balance minus the fee if `None`, to the treasury,
+    /// recording the outcome under `action`
+    async fn pay_treasury(
+        &self,
+        action: &str,
+        amount: Option<U256>,
+        confirm: bool,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        let treasury = self.controls.treasury;
+        let subject = match treasury {
+            Some(treasury) => format!("{treasury:?}"),
+            None => "no treasury".to_string(),
+        };
+        if !confirm {
+            audit::record(action, &subject, "rejected: not confirmed");
+            return Err(AdminError::<GatewaySigner<P>>::Unconfirmed.into());
+        }
+        if amount.is_none() {
+            if let Err(e) = self.check_quiescent().await {
+                audit::record(action, &subject, &format!("rejected: {e}"));
+                return Err(e.into());
+            }
+        }
+
+        let result = self.transfer(treasury, amount).await;
+        match &result {
+            Ok((value, r)) => audit::record(
+                action,
+                &subject,
+                &format!(
+                    "{:?}: {value} wei in transaction {:?}",
+                    r.status, r.transaction
+                ),
+            ),
+            Err(e) => audit::record(action, &subject, &format!("failed: {e}")),
+        }
+        Ok(result?.1)
+    }
+
+    /// Checks the gateway is paused and has no transaction pending, as the whole balance is only
+    /// known once nothing else spends from the wallet
+    async fn check_quiescent(&self) -> Result<(), AdminError<GatewaySigner<P>>> {
+        if !self.controls.pause.is_paused() {
+            return Err(AdminError::NotPaused);
+        }
+        let pending = self.signer.pending().prune(self.signer.as_ref()).await;
+        if !pending.is_empty() {
+            return Err(AdminError::TransactionsPending(pending.len()));
+        }
+        Ok(())
+    }
+
+    /// Sends the value given by [`transfer_value`] to `treasury`, returning it along with the
+    /// result of the transaction
+    async fn transfer(
+        &self,
+        treasury: Option<Address>,
+        amount: Option<U256>,
+    ) -> Result<(U256, OperationResult), AdminError<GatewaySigner<P>>> {
+        let treasury = treasury.ok_or(AdminError::NoTreasury)?;
+        let balance = self
+            .signer
+            .get_balance(self.signer.address(), None)
+            .await
+            .map_err(AdminError::Middleware)?;
+        let gas_price = self
+            .signer
+            .get_gas_price()
+            .await
+            .map_err(AdminError::Middleware)?;
+        let value = transfer_value(amount, balance, gas_price * TRANSFER_GAS)
+            .ok_or(AdminError::InsufficientFunds(balance))?;
+
+        // a legacy transaction at the current gas price, so the fee is known in advance
+        let tx = TransactionRequest::pay(treasury, value)
+            .gas(TRANSFER_GAS)
+            .gas_price(gas_price);
+        let receipt = self
+            .signer
+            .send_transaction(tx, None)
+            .await
+            .map_err(AdminError::Middleware)?
+            .await?;
+        let result = OperationResult::from_receipt("Funds sent to the treasury.", receipt);
+        Ok((value, result))
+    }
+}
+
+/// The value to transfer out of a wallet holding `balance`, paying `fee` for the transfer:
+/// `amount`, or everything left after the fee if `None`. `None` if the balance is insufficient.
+fn transfer_value(amount: Option<U256>, balance: U256, fee: U256) -> Option<U256> {
+    let value = amount.unwrap_or_else(|| balance.saturating_sub(fee));
+    let total = value.checked_add(fee)?;
+    (!value.is_zero() && total <= balance).then_some(value)
+}
+
+/// Raises the fees of `tx` by `percent`, rounding up so that even the smallest fees increase
+fn bump_fees(tx: &mut TypedTransaction, percent: u64) {
+    let bump = |fee: U256| fee + (fee * percent + 99) / 100;
+    match tx {
+        TypedTransaction::Legacy(tx) => tx.gas_price = tx.gas_price.map(bump),
+        TypedTransaction::Eip2930(tx) => tx.tx.gas_price = tx.tx.gas_price.map(bump),
+        TypedTransaction::Eip1559(tx) => {
+            tx.max_fee_per_gas = tx.max_fee_per_gas.map(bump);
+            tx.max_priority_fee_per_gas = tx.max_priority_fee_per_gas.map(bump);
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Middleware + 'static> AdminServer for AdminMethods<P> {
+    async fn pause(&self) -> Result<(), ErrorObjectOwned> {
+        if !self.controls.pause.pause() {
+            audit::record("pause", "gateway", "paused");
+        }
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<(), ErrorObjectOwned> {
+        if self.controls.pause.resume() {
+            audit::record("resume", "gateway", "resumed");
+        }
+        Ok(())
+    }
+
+    async fn status(&self) -> Result<AdminStatus, ErrorObjectOwned> {
+        Ok(AdminStatus {
+            paused: self.controls.pause.is_paused(),
+            pending_transactions: self
+                .signer
+                .pending()
+                .prune(self.signer.as_ref())
+                .await
+                .len(),
+        })
+    }
+
+    async fn pending_transactions(&self) -> Result<Vec<SentTransaction>, ErrorObjectOwned> {
+        Ok(self.signer.pending().prune(self.signer.as_ref()).await)
+    }
+
+    async fn cancel_transaction(
+        &self,
+        hash: H256,
+        percent: Option<u64>,
+    ) -> Result<H256, ErrorObjectOwned> {
+        let result = self.replace(hash, percent, true).await;
+        match &result {
+            Ok(replacement) => audit::record(
+                "cancel_transaction",
+                &format!("{hash:?}"),
+                &format!("replaced by {replacement:?}"),
+            ),
+            Err(e) => audit::record(
+                "cancel_transaction",
+                &format!("{hash:?}"),
+                &format!("failed: {e}"),
+            ),
+        }
+        Ok(result?)
+    }
+
+    async fn bump_transaction(
+        &self,
+        hash: H256,
+        percent: Option<u64>,
+    ) -> Result<H256, ErrorObjectOwned> {
+        let result = self.replace(hash, percent, false).await;
+        match &result {
+            Ok(replacement) => audit::record(
+                "bump_transaction",
+                &format!("{hash:?}"),
+                &format!("replaced by {replacement:?}"),
+            ),
+            Err(e) => audit::record(
+                "bump_transaction",
+                &format!("{hash:?}"),
+                &format!("failed: {e}"),
+            ),
+        }
+        Ok(result?)
+    }
+
+    async fn reload_policies(&self) -> Result<ReloadedPolicies, ErrorObjectOwned> {
+        let mut reloaded = ReloadedPolicies::default();
+        if let Some(path) = &self.controls.auth_file {
+            let policy = AuthPolicy::load(path).map_err(AdminError::<GatewaySigner<P>>::Reload)?;
+            if policy.operators.is_empty() {
+                let e = anyhow!("the new auth policy has no operators");
+                return Err(AdminError::<GatewaySigner<P>>::Reload(e).into());
+            }
+            self.controls.authenticator.reload(policy);
+            reloaded.auth = true;
+        }
+        if let Some(tls) = &self.controls.tls {
+            tls.reload()
+                .map_err(AdminError::<GatewaySigner<P>>::Reload)?;
+            reloaded.tls = true;
+        }
+        audit::record(
+            "reload_policies",
+            "gateway",
+            &format!("auth: {}, tls: {}", reloaded.auth, reloaded.tls),
+        );
+        Ok(reloaded)
+    }
+
+    async fn quota_usage(&self) -> Result<Vec<KeyUsage>, ErrorObjectOwned> {
+        Ok(self.controls.authenticator.usage())
+    }
+
+    async fn withdraw(
+        &self,
+        amount: U256,
+        confirm: bool,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        self.pay_treasury("withdraw", Some(amount), confirm).await
+    }
+
+    async fn sweep(&self, confirm: bool) -> Result<OperationResult, ErrorObjectOwned> {
+        self.pay_treasury("sweep", None, confirm).await
+    }
+}
+
+/// Error types for the admin namespace
+#[derive(Error, Debug)]
+pub(crate) enum AdminError<M: Middleware> {
+    #[error("No pending transaction {0:?}")]
+    UnknownTransaction(H256),
+    #[error("Fees must be bumped by {MIN_FEE_BUMP_PERCENT}% to {MAX_FEE_BUMP_PERCENT}%, not {0}%")]
+    InvalidBump(u64),
+    #[error("Failed to reload policies: {0}")]
+    Reload(anyhow::Error),
+    /// Funds were to be moved out of the gateway without confirmation
+    #[error("Moving funds out of the gateway wallet must be explicitly confirmed")]
+    Unconfirmed,
+    #[error("No treasury address is configured")]
+    NoTreasury,
+    #[error("Balance of {0} wei is insufficient")]
+    InsufficientFunds(U256),
+    #[error("The gateway must be paused before sweeping its wallet")]
+    NotPaused,
+    #[error("Pending transactions of the gateway must be mined or cancelled first ({0} left)")]
+    TransactionsPending(usize),
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+    #[error(transparent)]
+    Middleware(M::Error),
+}
+
+impl<M: Middleware> From<AdminError<M>> for ErrorObjectOwned {
+    fn from(error: AdminError<M>) -> Self {
+        let code = match &error {
+            AdminError::UnknownTransaction(_)
+            | AdminError::InvalidBump(_)
+            | AdminError::Unconfirmed => INVALID_PARAMS_CODE,
+            AdminError::Reload(_)
+            | AdminError::NoTreasury
+            | AdminError::InsufficientFunds(_)
+            | AdminError::NotPaused
+            | AdminError::TransactionsPending(_)
+            | AdminError::Provider(_)
+            | AdminError::Middleware(_) => GATEWAY_ERROR_CODE,
+        };
+        ErrorObjectOwned::owned(code, error.to_string(), None::<()>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    #[test]
+    fn test_transfer_value() {
+        let balance = U256::from(1000);
+        let fee = U256::from(100);
+        assert_eq!(
+            transfer_value(Some(U256::from(900)), balance, fee),
+            Some(U256::from(900))
+        );
+        assert_eq!(transfer_value(Some(U256::from(901)), balance, fee), None);
+        assert_eq!(transfer_value(Some(U256::MAX), balance, fee), None);
+        assert_eq!(transfer_value(None, balance, fee), Some(U256::from(900)));
+        assert_eq!(transfer_value(None, fee, fee), None);
+        assert_eq!(transfer_value(None, U256::zero(), fee), None);
+    }
+
+    #[test]
+    fn test_bump_fees() {
+        let mut legacy: TypedTransaction = TransactionRequest::new().gas_price(100).into();
+        bump_fees(&mut legacy, 20);
+        assert_eq!(legacy.gas_price(), Some(U256::from(120)));
+        let mut legacy: TypedTransaction = TransactionRequest::new().gas_price(101).into();
+        bump_fees(&mut legacy, 50);
+        assert_eq!(legacy.gas_price(), Some(U256::from(152)));
+
+        let mut eip1559: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(1000)
+            .max_priority_fee_per_gas(1)
+            .into();
+        bump_fees(&mut eip1559, 10);
+        let TypedTransaction::Eip1559(eip1559) = eip1559 else {
+            unreachable!()
+        };
+        assert_eq!(eip1559.max_fee_per_gas, Some(U256::from(1100)));
+        assert_eq!(eip1559.max_priority_fee_per_gas, Some(U256::from(2)));
+    }
+}
@@ -2,7 +2,8 @@
 
 use crate::types::{GatewayContext, GatewaySigner};
 
-use super::api::*;
+use super::XpsV2Server;
+use xps_types::rpc::*;
 
 use async_trait::async_trait;
 use ethers::prelude::*;
@@ -10,13 +11,15 @@ use ethers::{
     core::types::Signature,
     providers::{Middleware, ProviderError},
 };
-use jsonrpsee::types::ErrorObjectOwned;
-use lib_didethresolver::types::XmtpAttribute;
+use jsonrpsee::types::{error::INVALID_PARAMS_CODE, ErrorObjectOwned};
+use lib_didethresolver::types::{DidResolutionResult, XmtpAttribute};
 use messaging::MessagingOperations;
 use std::sync::Arc;
 use thiserror::Error;
 use xps_types::{
-    GrantInstallationResult, KeyPackageResult, Message, SendMessageResult, Unit, WalletBalance,
+    BatchInstallationResult, ChangeOwnerResult, DelegateResult, DelegateType,
+    ExpiringInstallationsResult, GrantInstallationResult, InstallationOperation, KeyPackageResult,
+    Message, PreparedDigest, ResolveDidOptions, SendMessageResult, Status, Unit, WalletBalance,
 };
 
 use messaging::error::MessagingOperationError;
@@ -28,8 +31,8 @@ pub const DEFAULT_ATTRIBUTE_VALIDITY: u64 = 60 * 60 * 24 * 365;
 
 /// Gateway Methods for XPS
 pub struct XpsMethods<P: Middleware + 'static> {
-    message_operations: MessagingOperations<GatewaySigner<P>>,
-    contact_operations: ContactOperations<GatewaySigner<P>>,
+    pub(crate) message_operations: MessagingOperations<GatewaySigner<P>>,
+    pub(crate) contact_operations: ContactOperations<GatewaySigner<P>>,
     pub signer: Arc<GatewaySigner<P>>,
 }
 
@@ -46,13 +49,9 @@ impl<P: Middleware> XpsMethods<P> {
 #[async_trait]
 impl<P: Middleware + 'static> XpsServer for XpsMethods<P> {
     async fn send_message(&self, message: Message) -> Result<SendMessageResult, ErrorObjectOwned> {
-        let result = self
-            .message_operations
-            .send_message(message)
+        XpsV2Server::send_message(self, message)
             .await
-            .map_err(RpcError::from)?;
-
-        Ok(result)
+            .map(Into::into)
     }
 
     async fn status(&self) -> Result<String, ErrorObjectOwned> {
@@ -67,17 +66,62 @@ impl<P: Middleware + 'static> XpsServer for XpsMethods<P> {
         value: Vec<u8>,
         signature: Signature,
     ) -> Result<GrantInstallationResult, ErrorObjectOwned> {
-        log::debug!("xps_grantInstallation called");
+        XpsV2Server::grant_installation(self, did, name, value, signature)
+            .await
+            .map(Into::into)
+    }
+
+    async fn revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<(), ErrorObjectOwned> {
+        let result = XpsV2Server::revoke_installation(self, did, name, value, signature).await?;
+        match result.status {
+            Status::Success => Ok(()),
+            // the v1 result has no status, so a failure is reported as an error
+            Status::Failed => Err(RpcError::<GatewaySigner<P>>::Failed(result.message).into()),
+        }
+    }
+
+    async fn renew_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<GrantInstallationResult, ErrorObjectOwned> {
+        XpsV2Server::renew_installation(self, did, name, value, signature)
+            .await
+            .map(Into::into)
+    }
+
+    async fn list_expiring_installations(
+        &self,
+        did: String,
+        within_seconds: u64,
+    ) -> Result<ExpiringInstallationsResult, ErrorObjectOwned> {
+        log::debug!("xps_listExpiringInstallations called");
+        let result = self
+            .contact_operations
+            .list_expiring_installations(did, within_seconds)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn batch_installations(
+        &self,
+        did: String,
+        operations: Vec<InstallationOperation>,
+    ) -> Result<BatchInstallationResult, ErrorObjectOwned> {
+        log::debug!("xps_batchInstallations called");
 
         let result = self
             .contact_operations
-            .grant_installation(
-                did,
-                name,
-                value,
-                signature,
-                U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
-            )
+            .batch_installations(did, operations, U256::from(DEFAULT_ATTRIBUTE_VALIDITY))
             .await;
 
         log::debug!("{:?}", result);
@@ -86,20 +130,51 @@ impl<P: Middleware + 'static> XpsServer for XpsMethods<P> {
         Ok(result)
     }
 
-    async fn revoke_installation(
+    async fn add_delegate(
         &self,
         did: String,
-        name: XmtpAttribute,
-        value: Vec<u8>,
+        delegate_type: DelegateType,
+        delegate: Address,
+        validity: u64,
         signature: Signature,
-    ) -> Result<(), ErrorObjectOwned> {
-        log::debug!("xps_revokeInstallation called");
-        self.contact_operations
-            .revoke_installation(did, name, value, signature)
+    ) -> Result<DelegateResult, ErrorObjectOwned> {
+        XpsV2Server::add_delegate(self, did, delegate_type, delegate, validity, signature)
             .await
-            .map_err(RpcError::from)?;
+            .map(Into::into)
+    }
 
-        Ok(())
+    async fn revoke_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+    ) -> Result<DelegateResult, ErrorObjectOwned> {
+        XpsV2Server::revoke_delegate(self, did, delegate_type, delegate, signature)
+            .await
+            .map(Into::into)
+    }
+
+    async fn change_owner(
+        &self,
+        did: String,
+        new_owner: Address,
+        signature: Signature,
+    ) -> Result<ChangeOwnerResult, ErrorObjectOwned> {
+        XpsV2Server::change_owner(self, did, new_owner, signature)
+            .await
+            .map(Into::into)
+    }
+
+    async fn deactivate_did(
+        &self,
+        did: String,
+        signature: Signature,
+        confirm: bool,
+    ) -> Result<ChangeOwnerResult, ErrorObjectOwned> {
+        XpsV2Server::deactivate_did(self, did, signature, confirm)
+            .await
+            .map(Into::into)
     }
 
     async fn wallet_address(&self) -> Result<Address, ErrorObjectOwned> {
@@ -146,6 +221,20 @@ impl<P: Middleware + 'static> XpsServer for XpsMethods<P> {
         Ok(result)
     }
 
+    async fn resolve_did(
+        &self,
+        did: String,
+        options: Option<ResolveDidOptions>,
+    ) -> Result<DidResolutionResult, ErrorObjectOwned> {
+        log::debug!("xps_resolveDid called");
+        let result = self
+            .contact_operations
+            .resolve_did(did, options.unwrap_or_default())
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
     async fn nonce(&self, did: String) -> Result<U256, ErrorObjectOwned> {
         log::debug!("xps_nonce called");
         let result = self
@@ -155,11 +244,69 @@ impl<P: Middleware + 'static> XpsServer for XpsMethods<P> {
             .map_err(RpcError::from)?;
         Ok(result)
     }
+
+    async fn message_nonce(&self, identity: Address) -> Result<U256, ErrorObjectOwned> {
+        log::debug!("xps_messageNonce called");
+        let result = self
+            .message_operations
+            .nonce(identity)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn prepare_send_message(
+        &self,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+        identity: Address,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        log::debug!("xps_prepareSendMessage called");
+        let result = self
+            .message_operations
+            .prepare_send_message(conversation_id, payload, identity)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn prepare_grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        log::debug!("xps_prepareGrantInstallation called");
+        let result = self
+            .contact_operations
+            .prepare_grant_installation(did, name, value, U256::from(DEFAULT_ATTRIBUTE_VALIDITY))
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn prepare_revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        log::debug!("xps_prepareRevokeInstallation called");
+        let result = self
+            .contact_operations
+            .prepare_revoke_installation(did, name, value)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
 }
 
+/// Error code of errors raised by the gateway while processing a request
+pub(crate) const GATEWAY_ERROR_CODE: i32 = -31999;
+
 /// Error types for DID Registry JSON-RPC
 #[derive(Debug, Error)]
-enum RpcError<M: Middleware> {
+pub(crate) enum RpcError<M: Middleware> {
     /// A public key parameter was invalid
     #[error(transparent)]
     Contact(#[from] ContactOperationError<M>),
@@ -168,14 +315,39 @@ enum RpcError<M: Middleware> {
     Balance(#[from] ProviderError),
     #[error(transparent)]
     Messaging(#[from] MessagingOperationError<M>),
+    /// The operation was submitted but did not succeed, for instance because its transaction
+    /// reverted
+    #[error("{0}")]
+    Failed(String),
+    /// An irreversible operation, such as `Deactivation`, was requested without confirmation
+    #[error("{0} is permanent and must be explicitly confirmed")]
+    Unconfirmed(&'static str),
 }
 
 impl<M: Middleware> From<RpcError<M>> for ErrorObjectOwned {
     fn from(error: RpcError<M>) -> Self {
         match error {
-            RpcError::Contact(c) => ErrorObjectOwned::owned(-31999, c.to_string(), None::<()>),
-            RpcError::Balance(c) => ErrorObjectOwned::owned(-31999, c.to_string(), None::<()>),
-            RpcError::Messaging(m) => ErrorObjectOwned::owned(-31999, m.to_string(), None::<()>),
+            RpcError::Contact(
+                c @ (ContactOperationError::ConflictingVersion
+                | ContactOperationError::VersionTime(_)
+                | ContactOperationError::VersionTimeBeforeEpoch
+                | ContactOperationError::VersionTimeBeforeGenesis),
+            ) => ErrorObjectOwned::owned(INVALID_PARAMS_CODE, c.to_string(), None::<()>),
+            RpcError::Contact(c) => {
+                ErrorObjectOwned::owned(GATEWAY_ERROR_CODE, c.to_string(), None::<()>)
+            }
+            RpcError::Balance(c) => {
+                ErrorObjectOwned::owned(GATEWAY_ERROR_CODE, c.to_string(), None::<()>)
+            }
+            RpcError::Messaging(m) => {
+                ErrorObjectOwned::owned(GATEWAY_ERROR_CODE, m.to_string(), None::<()>)
+            }
+            RpcError::Failed(message) => {
+                ErrorObjectOwned::owned(GATEWAY_ERROR_CODE, message, None::<()>)
+            }
+            e @ RpcError::Unconfirmed(_) => {
+                ErrorObjectOwned::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>)
+            }
         }
     }
 }
@@ -0,0 +1,264 @@
+//! Implementation of version 2 of the XPS JSON-RPC interface, served on the `xps_v2`
+//! namespace.
+//!
+//! The [`XpsV2`](super::XpsV2) trait lives in `xps-types` next to [`Xps`](super::Xps), so clients
+//! can call it as well. The methods of the `xps` namespace are implemented on top of these.
+
+use async_trait::async_trait;
+use ethers::{
+    core::types::Signature,
+    providers::Middleware,
+    types::{Address, Bytes, U256},
+};
+use jsonrpsee::types::ErrorObjectOwned;
+use lib_didethresolver::types::{DidResolutionResult, XmtpAttribute};
+use xps_types::{
+    DelegateType, ExpiringInstallationsResult, InstallationOperation, KeyPackageResult, Message,
+    OperationResult, PreparedDigest, ResolveDidOptions, WalletBalance,
+};
+
+use super::{methods::RpcError, XpsMethods, XpsServer, XpsV2Server, DEFAULT_ATTRIBUTE_VALIDITY};
+use crate::audit;
+
+#[async_trait]
+impl<P: Middleware + 'static> XpsV2Server for XpsMethods<P> {
+    async fn send_message(&self, message: Message) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_sendMessage called");
+        let result = self
+            .message_operations
+            .send_message(message)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_grantInstallation called");
+
+        let result = self
+            .contact_operations
+            .grant_installation(
+                did,
+                name,
+                value,
+                signature,
+                U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+            )
+            .await;
+
+        log::debug!("{:?}", result);
+        let result = result.map_err(RpcError::from)?;
+
+        Ok(result)
+    }
+
+    async fn revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_revokeInstallation called");
+        let result = self
+            .contact_operations
+            .revoke_installation(did, name, value, signature)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn renew_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_renewInstallation called");
+
+        let result = self
+            .contact_operations
+            .renew_installation(
+                did,
+                name,
+                value,
+                signature,
+                U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+            )
+            .await;
+
+        log::debug!("{:?}", result);
+        let result = result.map_err(RpcError::from)?;
+
+        Ok(result)
+    }
+
+    async fn list_expiring_installations(
+        &self,
+        did: String,
+        within_seconds: u64,
+    ) -> Result<ExpiringInstallationsResult, ErrorObjectOwned> {
+        XpsServer::list_expiring_installations(self, did, within_seconds).await
+    }
+
+    async fn batch_installations(
+        &self,
+        did: String,
+        operations: Vec<InstallationOperation>,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        XpsServer::batch_installations(self, did, operations)
+            .await
+            .map(Into::into)
+    }
+
+    async fn add_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        validity: u64,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_addDelegate called");
+        let result = self
+            .contact_operations
+            .add_delegate(
+                did,
+                delegate_type,
+                delegate,
+                signature,
+                U256::from(validity),
+            )
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn revoke_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_revokeDelegate called");
+        let result = self
+            .contact_operations
+            .revoke_delegate(did, delegate_type, delegate, signature)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn change_owner(
+        &self,
+        did: String,
+        new_owner: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_changeOwner called");
+        let result = self
+            .contact_operations
+            .change_owner(did, new_owner, signature)
+            .await
+            .map_err(RpcError::from)?;
+        Ok(result)
+    }
+
+    async fn deactivate_did(
+        &self,
+        did: String,
+        signature: Signature,
+        confirm: bool,
+    ) -> Result<OperationResult, ErrorObjectOwned> {
+        log::debug!("xps_v2_deactivateDid called");
+        if !confirm {
+            audit::record("deactivate_did", &did, "rejected: not confirmed");
+            return Err(RpcError::<P>::Unconfirmed("Deactivation").into());
+        }
+
+        let result = self
+            .contact_operations
+            .deactivate_did(did.clone(), signature)
+            .await;
+
+        match &result {
+            Ok(r) => audit::record(
+                "deactivate_did",
+                &did,
+                &format!("deactivated in transaction {:?}", r.transaction),
+            ),
+            Err(e) => audit::record("deactivate_did", &did, &format!("failed: {}", e)),
+        }
+
+        let result = result.map_err(RpcError::from)?;
+
+        Ok(result)
+    }
+
+    async fn fetch_key_packages(&self, did: String) -> Result<KeyPackageResult, ErrorObjectOwned> {
+        XpsServer::fetch_key_packages(self, did).await
+    }
+
+    async fn resolve_did(
+        &self,
+        did: String,
+        options: Option<ResolveDidOptions>,
+    ) -> Result<DidResolutionResult, ErrorObjectOwned> {
+        XpsServer::resolve_did(self, did, options).await
+    }
+
+    async fn status(&self) -> Result<String, ErrorObjectOwned> {
+        XpsServer::status(self).await
+    }
+
+    async fn wallet_address(&self) -> Result<Address, ErrorObjectOwned> {
+        XpsServer::wallet_address(self).await
+    }
+
+    async fn balance(&self) -> Result<WalletBalance, ErrorObjectOwned> {
+        XpsServer::balance(self).await
+    }
+
+    async fn nonce(&self, did: String) -> Result<U256, ErrorObjectOwned> {
+        XpsServer::nonce(self, did).await
+    }
+
+    async fn message_nonce(&self, identity: Address) -> Result<U256, ErrorObjectOwned> {
+        XpsServer::message_nonce(self, identity).await
+    }
+
+    async fn prepare_send_message(
+        &self,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+        identity: Address,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        XpsServer::prepare_send_message(self, conversation_id, payload, identity).await
+    }
+
+    async fn prepare_grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        XpsServer::prepare_grant_installation(self, did, name, value).await
+    }
+
+    async fn prepare_revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned> {
+        XpsServer::prepare_revoke_installation(self, did, name, value).await
+    }
+}
@@ -0,0 +1,227 @@
+//! Graceful shutdown of the gateway on SIGINT or SIGTERM.
+//!
+//! Once shutdown begins, new calls are rejected with a "server is busy" error while calls
+//! already in flight are given until a deadline to complete. Transactions the gateway broadcast
+//! and which are still not mined by then are persisted, see [`crate::pending`], so that an
+//! operator can follow up on them.
+
+use std::{
+    fmt,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use ethers::types::H256;
+use jsonrpsee::{
+    server::middleware::rpc::RpcServiceT,
+    types::{error::SERVER_IS_BUSY_CODE, ErrorObject, Request},
+    MethodResponse,
+};
+use tokio::sync::Notify;
+
+/// Default time given to in-flight calls to complete once shutdown begins
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Tracks the calls in flight, and whether new calls are still accepted
+#[derive(Debug, Default)]
+pub struct Drain {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl Drain {
+    /// Stops accepting new calls
+    pub fn begin(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Number of calls being served
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits up to `timeout` for the calls in flight to complete, returning whether they did
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    /// Registers a new call, unless draining
+    fn enter(self: &Arc<Self>) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let call = InFlight(self.clone());
+        (!self.is_draining()).then_some(call)
+    }
+}
+
+/// A call being served, until dropped
+struct InFlight(Arc<Drain>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// RPC middleware rejecting calls once shutdown began, and tracking the others until they
+/// complete
+#[derive(Debug, Clone)]
+pub struct Draining<S> {
+    inner: S,
+    drain: Arc<Drain>,
+}
+
+impl<S> Draining<S> {
+    pub fn new(inner: S, drain: Arc<Drain>) -> Self {
+        Self { inner, drain }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for Draining<S>
+where
+    S: RpcServiceT<'a> + Send + Sync + Clone + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let Some(call) = self.drain.enter() else {
+            let error =
+                ErrorObject::owned(SERVER_IS_BUSY_CODE, "Gateway is shutting down", None::<()>);
+            let response = MethodResponse::error(request.id, error);
+            return Box::pin(async move { response });
+        };
+        let service = self.inner.clone();
+        Box::pin(async move {
+            let response = service.call(request).await;
+            drop(call);
+            response
+        })
+    }
+}
+
+/// Waits for SIGINT or SIGTERM, returning the name of the signal received
+#[cfg(unix)]
+pub async fn signal() -> Result<&'static str> {
+    use tokio::signal::unix::{self, SignalKind};
+
+    let mut terminate = unix::signal(SignalKind::terminate())?;
+    let name = tokio::select! {
+        interrupt = tokio::signal::ctrl_c() => {
+            interrupt?;
+            "SIGINT"
+        }
+        _ = terminate.recv() => "SIGTERM",
+    };
+    Ok(name)
+}
+
+/// Waits for Ctrl-C, the only shutdown signal outside of unix
+#[cfg(not(unix))]
+pub async fn signal() -> Result<&'static str> {
+    tokio::signal::ctrl_c().await?;
+    Ok("Ctrl-C")
+}
+
+/// What was left unfinished when the gateway stopped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShutdownSummary {
+    /// Calls still in flight at the deadline
+    pub interrupted_calls: usize,
+    /// Transactions broadcast by the gateway and not mined
+    pub pending_transactions: Vec<H256>,
+    /// Where the pending transactions were written, if anywhere
+    pub pending_file: Option<PathBuf>,
+}
+
+impl fmt::Display for ShutdownSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.interrupted_calls == 0 {
+            write!(f, "all calls completed")?;
+        } else {
+            write!(f, "{} calls interrupted", self.interrupted_calls)?;
+        }
+        match (self.pending_transactions.len(), &self.pending_file) {
+            (0, _) => write!(f, ", no pending transactions"),
+            (count, Some(path)) => {
+                write!(
+                    f,
+                    ", {count} pending transactions written to {}",
+                    path.display()
+                )
+            }
+            (count, None) => {
+                write!(f, ", {count} pending transactions:")?;
+                for hash in &self.pending_transactions {
+                    write!(f, " {hash:?}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain() {
+        let drain = Arc::new(Drain::default());
+        let call = drain.enter().unwrap();
+        assert_eq!(drain.in_flight(), 1);
+
+        drain.begin();
+        assert!(drain.enter().is_none());
+        assert_eq!(drain.in_flight(), 1);
+        assert!(!drain.wait_idle(Duration::from_millis(10)).await);
+
+        let waiting = tokio::spawn({
+            let drain = drain.clone();
+            async move { drain.wait_idle(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(call);
+        assert!(waiting.await.unwrap());
+        assert_eq!(drain.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            ShutdownSummary::default().to_string(),
+            "all calls completed, no pending transactions"
+        );
+        let summary = ShutdownSummary {
+            interrupted_calls: 2,
+            pending_transactions: vec![H256::zero()],
+            pending_file: Some(PathBuf::from("pending.json")),
+        };
+        assert_eq!(
+            summary.to_string(),
+            "2 calls interrupted, 1 pending transactions written to pending.json"
+        );
+    }
+}
@@ -0,0 +1,508 @@
+//! Optional TLS termination for the JSON-RPC server, for deployments without a reverse proxy.
+//!
+//! The certificate and key are read from PEM files, and read again when the process receives
+//! `SIGHUP` on unix, or on `admin_reloadPolicies`, so renewed certificates are picked up without a
+//! restart. Connections established before a reload keep the certificate they were established
+//! with.
+//!
+//! With a client CA, clients may present a certificate signed by it. Requests on such
+//! connections are marked with [`ClientCertificate`], which [`RequireClientCertLayer`] requires
+//! for the operator-only endpoints: [`OPERATOR_PATHS`] on the public server, and every request
+//! to the admin server. The JSON-RPC methods of the public server never require one. Without a
+//! client CA, no request requires a client certificate.
+
+use std::{
+    fs::File,
+    future::Future,
+    io::BufReader,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use hyper::{
+    body::HttpBody,
+    header::{HeaderValue, CONTENT_TYPE},
+    Body, Request, Response, StatusCode,
+};
+use jsonrpsee::server::StopHandle;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        server::AllowAnyAnonymousOrAuthenticatedClient, Certificate, PrivateKey, RootCertStore,
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tower::{Layer, Service};
+
+use crate::metrics;
+
+/// Endpoints of the public server reserved to operators presenting a client certificate, when a
+/// client CA is set: only the metrics. This comes on top of the operator credentials
+/// [`crate::auth::AuthLayer`] requires for them under an auth policy.
+pub const OPERATOR_PATHS: &[&str] = &[metrics::METRICS_PATH];
+
+/// Every path, for servers reserved to operators altogether, such as the admin server
+pub const EVERY_PATH: &[&str] = &["/"];
+
+/// Time given to a client to complete the TLS handshake before the connection is closed
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Files to terminate TLS with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// PEM encoded certificate chain
+    pub cert: PathBuf,
+    /// PEM encoded private key
+    pub key: PathBuf,
+    /// PEM encoded certificates of the CAs signing the certificates of operators, if any. Only
+    /// `/metrics` and the admin server require a client certificate, see [`OPERATOR_PATHS`].
+    pub client_ca: Option<PathBuf>,
+}
+
+/// The TLS configuration currently in use, see [`TlsConfig::reload`]
+#[derive(Debug)]
+pub struct TlsConfig {
+    options: TlsOptions,
+    current: RwLock<Arc<ServerConfig>>,
+}
+
+impl TlsConfig {
+    /// Reads the files of `options`
+    pub fn load(options: TlsOptions) -> Result<Self> {
+        let current = RwLock::new(Arc::new(server_config(&options)?));
+        Ok(Self { options, current })
+    }
+
+    /// Reads the files again. On error, the previous configuration is kept.
+    pub fn reload(&self) -> Result<()> {
+        let config = server_config(&self.options)?;
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+        Ok(())
+    }
+
+    /// Acceptor for new connections, with the current configuration
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(
+            self.current
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        )
+    }
+
+    /// Whether clients may authenticate with a certificate
+    pub fn verifies_clients(&self) -> bool {
+        self.options.client_ca.is_some()
+    }
+}
+
+/// Reloads `config` every time the process receives `SIGHUP`
+#[cfg(unix)]
+pub fn reload_on_sighup(config: Arc<TlsConfig>) -> Result<()> {
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match config.reload() {
+                Ok(()) => log::info!("Reloaded TLS certificate"),
+                Err(e) => {
+                    log::error!("Failed to reload TLS certificate, keeping the previous one: {e:#}")
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Serves the connections accepted by `listener` over TLS, each with a service made by
+/// `make_service`, until `stop_handle` is stopped. Connections not completing the handshake
+/// within `handshake_timeout` are closed. Requests of connections authenticated with a client
+/// certificate are marked with [`ClientCertificate`].
+pub(crate) fn serve<F, S, B>(
+    listener: TcpListener,
+    config: Arc<TlsConfig>,
+    stop_handle: StopHandle,
+    handshake_timeout: Duration,
+    make_service: F,
+) where
+    F: Fn() -> S + Send + 'static,
+    S: Service<Request<Body>, Response = Response<B>> + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    tokio::spawn(async move {
+        let stopped = stop_handle.shutdown();
+        tokio::pin!(stopped);
+        loop {
+            let (stream, remote) = tokio::select! {
+                _ = &mut stopped => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        log::warn!("Failed to accept a connection: {e}");
+                        continue;
+                    }
+                },
+            };
+            let acceptor = config.acceptor();
+            let service = make_service();
+            tokio::spawn(async move {
+                let stream =
+                    match tokio::time::timeout(handshake_timeout, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => stream,
+                        Ok(Err(e)) => {
+                            log::debug!("TLS handshake with {remote} failed: {e}");
+                            return;
+                        }
+                        Err(_) => {
+                            log::debug!("TLS handshake with {remote} timed out");
+                            return;
+                        }
+                    };
+                let verified = stream.get_ref().1.peer_certificates().is_some();
+                let service = WithClientCertificate::new(service, verified);
+                if let Err(e) = hyper::server::conn::Http::new()
+                    .serve_connection(stream, service)
+                    .with_upgrades()
+                    .await
+                {
+                    log::debug!("Connection with {remote} failed: {e}");
+                }
+            });
+        }
+    });
+}
+
+/// Without `SIGHUP`, the certificates are only reloaded by `admin_reloadPolicies`
+#[cfg(not(unix))]
+pub fn reload_on_sighup(_config: Arc<TlsConfig>) -> Result<()> {
+    log::info!("TLS certificates are reloaded by admin_reloadPolicies only on this platform");
+    Ok(())
+}
+
+fn server_config(options: &TlsOptions) -> Result<ServerConfig> {
+    let certs = read_certs(&options.cert)?;
+    let key = read_key(&options.key)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &options.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(path)? {
+                roots.add(&cert)?;
+            }
+            builder.with_client_cert_verifier(
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+            )
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder.with_single_cert(certs, key)?;
+    // websockets are upgraded from HTTP/1.1 only
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+fn read_certs(path: &Path) -> Result<Vec<Certificate>> {
+    let file = File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))?;
+    if certs.is_empty() {
+        return Err(anyhow!("no certificate in {}", path.display()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_key(path: &Path) -> Result<PrivateKey> {
+    let file = File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    rustls_pemfile::read_all(&mut BufReader::new(file))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("no private key in {}", path.display()))
+}
+
+/// Extension of the requests made on a connection authenticated with a client certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientCertificate;
+
+/// Service marking the requests of a connection with [`ClientCertificate`], if `verified`
+#[derive(Debug, Clone)]
+pub struct WithClientCertificate<S> {
+    inner: S,
+    verified: bool,
+}
+
+impl<S> WithClientCertificate<S> {
+    pub fn new(inner: S, verified: bool) -> Self {
+        Self { inner, verified }
+    }
+}
+
+impl<S> Service<Request<Body>> for WithClientCertificate<S>
+where
+    S: Service<Request<Body>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        if self.verified {
+            request.extensions_mut().insert(ClientCertificate);
+        }
+        self.inner.call(request)
+    }
+}
+
+/// Layer rejecting requests to some paths unless they carry a [`ClientCertificate`]
+#[derive(Debug, Clone, Copy)]
+pub struct RequireClientCertLayer {
+    paths: &'static [&'static str],
+}
+
+impl RequireClientCertLayer {
+    /// Requires a client certificate for the paths starting with any of `paths`
+    pub fn new(paths: &'static [&'static str]) -> Self {
+        Self { paths }
+    }
+}
+
+impl<S> Layer<S> for RequireClientCertLayer {
+    type Service = RequireClientCert<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireClientCert {
+            inner,
+            paths: self.paths,
+        }
+    }
+}
+
+/// Service enforcing client certificates, see [`RequireClientCertLayer`]
+#[derive(Debug, Clone)]
+pub struct RequireClientCert<S> {
+    inner: S,
+    paths: &'static [&'static str],
+}
+
+impl<S> Service<Request<Body>> for RequireClientCert<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: Into<BoxError> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let path = request.uri().path();
+        let restricted = self.paths.iter().any(|prefix| path.starts_with(prefix));
+        if restricted && request.extensions().get::<ClientCertificate>().is_none() {
+            let body = json!({ "code": 403, "message": "Client certificate required" });
+            let mut response = Response::new(Body::from(body.to_string()));
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            return Box::pin(async { Ok(response) });
+        }
+        let response = self.inner.call(request);
+        Box::pin(async move { response.await.map_err(Into::into) })
+    }
+}
+
+/// A TLS client trusting the certificates of the test CA, for the tests of the servers
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use std::net::SocketAddr;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_rustls::{
+        rustls::{ClientConfig, ServerName},
+        TlsConnector,
+    };
+
+    pub(crate) fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    /// Options serving the test server certificate, verifying the client certificates signed by
+    /// the test CA
+    pub(crate) fn test_ca_options() -> TlsOptions {
+        TlsOptions {
+            cert: fixture("test-server.pem"),
+            key: fixture("test-server-key.pem"),
+            client_ca: Some(fixture("test-ca.pem")),
+        }
+    }
+
+    /// Sends the raw HTTP/1.1 `request` to `addr` over TLS, presenting the test client
+    /// certificate if `client_cert`, and returns the raw response
+    pub(crate) async fn send(addr: SocketAddr, client_cert: bool, request: &str) -> String {
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(&fixture("test-ca.pem")).unwrap() {
+            roots.add(&cert).unwrap();
+        }
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let config = if client_cert {
+            builder
+                .with_client_auth_cert(
+                    read_certs(&fixture("test-client.pem")).unwrap(),
+                    read_key(&fixture("test-client-key.pem")).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        // the server may close the connection without a close_notify
+        let _ = stream.read_to_end(&mut response).await;
+        String::from_utf8(response).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{testing::*, *};
+    use jsonrpsee::server::{stop_channel, ServerHandle};
+    use std::{convert::Infallible, net::SocketAddr};
+    use tokio::{io::AsyncReadExt, net::TcpStream};
+    use tower::{service_fn, ServiceExt};
+
+    fn options(client_ca: bool) -> TlsOptions {
+        TlsOptions {
+            cert: fixture("localhost.pem"),
+            key: fixture("localhost-key.pem"),
+            client_ca: client_ca.then(|| fixture("localhost.pem")),
+        }
+    }
+
+    #[test]
+    fn test_load_and_reload() -> Result<()> {
+        let config = TlsConfig::load(options(false))?;
+        assert!(!config.verifies_clients());
+        config.reload()?;
+
+        let config = TlsConfig::load(options(true))?;
+        assert!(config.verifies_clients());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut missing = options(false);
+        missing.cert = fixture("missing.pem");
+        assert!(TlsConfig::load(missing).is_err());
+
+        let mut swapped = options(false);
+        swapped.key = fixture("localhost.pem");
+        assert!(TlsConfig::load(swapped).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_require_client_cert() {
+        let service = RequireClientCertLayer::new(OPERATOR_PATHS).layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+        let request = |path: &str| Request::builder().uri(path).body(Body::empty()).unwrap();
+
+        let response = service.clone().oneshot(request("/metrics")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = service.clone().oneshot(request("/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = WithClientCertificate::new(service, true)
+            .oneshot(request("/metrics"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Serves TLS signed by the test CA, verifying the client certificates it signed, answering
+    /// whether requests were marked with [`ClientCertificate`]
+    async fn serve_marking(handshake_timeout: Duration) -> (SocketAddr, ServerHandle) {
+        let config = TlsConfig::load(test_ca_options()).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop_handle, handle) = stop_channel();
+        serve(
+            listener,
+            Arc::new(config),
+            stop_handle,
+            handshake_timeout,
+            || {
+                service_fn(|request: Request<Body>| async move {
+                    let marked = request.extensions().get::<ClientCertificate>().is_some();
+                    let body = if marked { "verified" } else { "anonymous" };
+                    Ok::<_, Infallible>(Response::new(Body::from(body)))
+                })
+            },
+        );
+        (addr, handle)
+    }
+
+    /// Body of `GET /` on `addr`, presenting the test client certificate if `client_cert`
+    async fn get(addr: SocketAddr, client_cert: bool) -> String {
+        let request = "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+        let response = send(addr, client_cert, request).await;
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        body.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_serve_marks_client_certificates() {
+        let (addr, handle) = serve_marking(HANDSHAKE_TIMEOUT).await;
+        assert_eq!(get(addr, true).await, "verified");
+        assert_eq!(get(addr, false).await, "anonymous");
+        handle.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_serve_handshake_timeout() {
+        let (addr, handle) = serve_marking(Duration::from_millis(100)).await;
+        // connect without ever starting the handshake
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0; 1];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("the connection should be closed by the server");
+        assert!(matches!(read, Ok(0) | Err(_)));
+        handle.stop().unwrap();
+    }
+}
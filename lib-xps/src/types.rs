@@ -1,6 +1,6 @@
-use std::sync::Arc;
+use std::{fs, io::Write, path::Path, sync::Arc};
 
-use anyhow::Error;
+use anyhow::{Context, Error};
 use ethers::{
     middleware::SignerMiddleware, providers::Middleware, signers::LocalWallet, types::Address,
 };
@@ -8,7 +8,10 @@ use lib_didethresolver::did_registry::DIDRegistry;
 use messaging::Conversation;
 use rand::{rngs::StdRng, SeedableRng};
 
-pub type GatewaySigner<P> = SignerMiddleware<P, LocalWallet>;
+use crate::pending::PendingTracker;
+
+/// The client relaying transactions, signing with the gateway wallet and tracking what it sent
+pub type GatewaySigner<P> = PendingTracker<SignerMiddleware<P, LocalWallet>>;
 
 pub struct GatewayContext<P: Middleware> {
     pub registry: DIDRegistry<GatewaySigner<P>>,
@@ -17,10 +20,22 @@ pub struct GatewayContext<P: Middleware> {
 }
 
 impl<P: Middleware + 'static> GatewayContext<P> {
+    /// Creates a context signing with a new wallet, which is lost when the process exits
     pub async fn new(registry: Address, conversation: Address, provider: P) -> Result<Self, Error> {
         let wallet = LocalWallet::new(&mut StdRng::from_entropy());
-        let signer =
-            Arc::new(SignerMiddleware::new_with_provider_chain(provider, wallet.clone()).await?);
+        Self::with_wallet(registry, conversation, provider, wallet).await
+    }
+
+    /// Creates a context signing with `wallet`
+    pub async fn with_wallet(
+        registry: Address,
+        conversation: Address,
+        provider: P,
+        wallet: LocalWallet,
+    ) -> Result<Self, Error> {
+        let signer = Arc::new(PendingTracker::new(
+            SignerMiddleware::new_with_provider_chain(provider, wallet.clone()).await?,
+        ));
         let registry = DIDRegistry::new(registry, signer.clone());
         let conversation = Conversation::new(conversation, signer.clone());
         Ok(Self {
@@ -31,6 +46,32 @@ impl<P: Middleware + 'static> GatewayContext<P> {
     }
 }
 
+/// Reads the hex encoded private key of the gateway wallet from `path`, or generates a wallet
+/// and writes its key there, readable by the current user only, if the file does not exist
+pub fn load_or_create_wallet<T: AsRef<Path>>(path: T) -> Result<LocalWallet, Error> {
+    let path = path.as_ref();
+    if path.exists() {
+        let key = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the wallet key {}", path.display()))?;
+        return key
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid wallet key in {}", path.display()));
+    }
+
+    let wallet = LocalWallet::new(&mut StdRng::from_entropy());
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create the wallet key {}", path.display()))?;
+    writeln!(file, "{}", hex::encode(wallet.signer().to_bytes()))?;
+    log::info!("Created the gateway wallet key {}", path.display());
+    Ok(wallet)
+}
+
 #[cfg(test)]
 mod tests {
     use ethers::{providers::Provider, types::U64};
@@ -55,4 +96,15 @@ mod tests {
         assert!(gateway.conversation.address().is_zero());
         assert!(gateway.signer.is_signer().await);
     }
+
+    #[test]
+    fn test_load_or_create_wallet() {
+        use ethers::signers::Signer;
+
+        let path = std::env::temp_dir().join(format!("xps-wallet-{}.key", std::process::id()));
+        let created = load_or_create_wallet(&path).unwrap();
+        let loaded = load_or_create_wallet(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(created.address(), loaded.address());
+    }
 }
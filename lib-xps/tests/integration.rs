@@ -1,6 +1,7 @@
 mod integration_util;
 
 mod it {
+    mod admin;
     mod contact_ops;
     mod messaging;
     mod xps;
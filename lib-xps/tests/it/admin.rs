@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Error;
+use ethers::providers::Middleware;
+use ethers::types::{Address, TransactionRequest, H256, U256};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use tracing::{
+    field::{Field, Visit},
+    instrument::WithSubscriber,
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    Layer, Registry,
+};
+
+use crate::integration_util::*;
+use lib_xps::{
+    admin::{Controls, Pause},
+    auth::{AuthPolicy, Authenticator},
+    rpc::admin::{AdminMethods, AdminServer},
+    types::GatewayContext,
+};
+use xps_types::Status;
+
+/// Records emitted on the `xps::audit` target, as (action, subject, outcome)
+#[derive(Clone, Default)]
+struct AuditLog(Arc<Mutex<Vec<(String, String, String)>>>);
+
+impl AuditLog {
+    fn records(&self) -> Vec<(String, String, String)> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AuditLog {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        if event.metadata().target() != "xps::audit" {
+            return;
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let mut field = |name: &str| fields.0.remove(name).unwrap_or_default();
+        let record = (field("action"), field("subject"), field("outcome"));
+        self.0.lock().unwrap().push(record);
+    }
+}
+
+#[derive(Default)]
+struct Fields(HashMap<String, String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+fn admin_methods<P: Middleware + 'static>(
+    context: &GatewayContext<P>,
+    treasury: Option<Address>,
+) -> (AdminMethods<P>, Arc<Pause>) {
+    let pause = Arc::new(Pause::default());
+    let controls = Controls {
+        pause: pause.clone(),
+        authenticator: Arc::new(Authenticator::new(AuthPolicy::default())),
+        auth_file: None,
+        tls: None,
+        treasury,
+    };
+    (AdminMethods::new(context, controls), pause)
+}
+
+#[tokio::test]
+async fn test_withdraw() -> Result<(), Error> {
+    with_xps_client(None, None, |_, context, _, anvil| async move {
+        let audit = AuditLog::default();
+        let subscriber = Registry::default().with(audit.clone());
+        async move {
+            let treasury = anvil.addresses()[5];
+            let (admin, _) = admin_methods(&context, Some(treasury));
+            let before = context.signer.get_balance(treasury, None).await?;
+            let amount = U256::exp10(18);
+
+            let result = admin.withdraw(amount, true).await?;
+            assert_eq!(result.status, Status::Success);
+            assert!(result.transaction.is_some());
+            assert!(result.block_number.is_some());
+            assert_eq!(
+                context.signer.get_balance(treasury, None).await?,
+                before + amount
+            );
+
+            let records = audit.records();
+            assert_eq!(records.len(), 1);
+            let (action, subject, outcome) = &records[0];
+            assert_eq!(action, "withdraw");
+            assert_eq!(subject, &format!("{treasury:?}"));
+            assert!(outcome.starts_with(&format!("Success: {amount} wei")));
+            Ok(())
+        }
+        .with_subscriber(subscriber)
+        .await
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_sweep() -> Result<(), Error> {
+    with_xps_client(None, None, |_, context, _, anvil| async move {
+        let audit = AuditLog::default();
+        let subscriber = Registry::default().with(audit.clone());
+        async move {
+            let treasury = anvil.addresses()[5];
+            let (admin, pause) = admin_methods(&context, Some(treasury));
+            pause.pause();
+
+            let result = admin.sweep(true).await?;
+            assert_eq!(result.status, Status::Success);
+            assert_eq!(
+                context
+                    .signer
+                    .get_balance(context.signer.address(), None)
+                    .await?,
+                U256::zero()
+            );
+
+            let records = audit.records();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].0, "sweep");
+            assert!(records[0].2.starts_with("Success"));
+            Ok(())
+        }
+        .with_subscriber(subscriber)
+        .await
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_sweep_requires_quiescence() -> Result<(), Error> {
+    with_xps_client(None, None, |_, context, _, anvil| async move {
+        let audit = AuditLog::default();
+        let subscriber = Registry::default().with(audit.clone());
+        async move {
+            let treasury = anvil.addresses()[5];
+            let (admin, pause) = admin_methods(&context, Some(treasury));
+            let balance = context
+                .signer
+                .get_balance(context.signer.address(), None)
+                .await?;
+
+            let error = admin.sweep(true).await.unwrap_err();
+            assert_eq!(
+                error.message(),
+                "The gateway must be paused before sweeping its wallet"
+            );
+
+            // a transaction anvil never saw, so it is never mined
+            pause.pause();
+            let hash = H256::repeat_byte(1);
+            context
+                .signer
+                .pending()
+                .insert(hash, TransactionRequest::new().into());
+            let error = admin.sweep(true).await.unwrap_err();
+            assert_eq!(
+                error.message(),
+                "Pending transactions of the gateway must be mined or cancelled first (1 left)"
+            );
+            assert_eq!(
+                context
+                    .signer
+                    .get_balance(context.signer.address(), None)
+                    .await?,
+                balance
+            );
+
+            let records = audit.records();
+            assert_eq!(records.len(), 2);
+            assert!(records
+                .iter()
+                .all(|(action, _, outcome)| action == "sweep" && outcome.starts_with("rejected")));
+
+            context.signer.pending().remove(&hash);
+            assert_eq!(admin.sweep(true).await?.status, Status::Success);
+            Ok(())
+        }
+        .with_subscriber(subscriber)
+        .await
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_withdraw_rejections() -> Result<(), Error> {
+    with_xps_client(None, None, |_, context, _, anvil| async move {
+        let audit = AuditLog::default();
+        let subscriber = Registry::default().with(audit.clone());
+        async move {
+            let treasury = anvil.addresses()[5];
+            let before = context.signer.get_balance(treasury, None).await?;
+
+            let (admin, pause) = admin_methods(&context, Some(treasury));
+            pause.pause();
+            let error = admin.withdraw(U256::exp10(18), false).await.unwrap_err();
+            assert_eq!(error.code(), INVALID_PARAMS_CODE);
+            let error = admin.sweep(false).await.unwrap_err();
+            assert_eq!(error.code(), INVALID_PARAMS_CODE);
+
+            let (admin, _) = admin_methods(&context, None);
+            let error = admin.withdraw(U256::exp10(18), true).await.unwrap_err();
+            assert_eq!(error.message(), "No treasury address is configured");
+
+            assert_eq!(context.signer.get_balance(treasury, None).await?, before);
+            assert_eq!(
+                audit.records(),
+                vec![
+                    (
+                        "withdraw".to_string(),
+                        format!("{treasury:?}"),
+                        "rejected: not confirmed".to_string()
+                    ),
+                    (
+                        "sweep".to_string(),
+                        format!("{treasury:?}"),
+                        "rejected: not confirmed".to_string()
+                    ),
+                    (
+                        "withdraw".to_string(),
+                        "no treasury".to_string(),
+                        "failed: No treasury address is configured".to_string()
+                    ),
+                ]
+            );
+            Ok(())
+        }
+        .with_subscriber(subscriber)
+        .await
+    })
+    .await
+}
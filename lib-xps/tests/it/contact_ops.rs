@@ -3,15 +3,21 @@ use std::str::FromStr;
 use anyhow::Error;
 
 use crate::integration_util::*;
+use ethers::providers::Middleware;
 use ethers::types::{Address, U256};
 use ethers::{signers::LocalWallet, signers::Signer};
-use jsonrpsee::core::ClientError;
+use jsonrpsee::{core::ClientError, types::error::INVALID_PARAMS_CODE};
 use lib_didethresolver::{
     did_registry::RegistrySignerExt,
     types::{DidUrl, KeyEncoding, XmtpAttribute, XmtpKeyPurpose, NULL_ADDRESS},
 };
 use lib_xps::rpc::{XpsClient, DEFAULT_ATTRIBUTE_VALIDITY};
-use xps_types::Status;
+use xps_types::{
+    digest::{
+        add_delegate_digest, revoke_attribute_digest, revoke_delegate_digest, set_attribute_digest,
+    },
+    DelegateType, InstallationOperation, InstallationOperationKind, ResolveDidOptions, Status,
+};
 
 #[tokio::test]
 async fn test_grant_revoke() -> Result<(), Error> {
@@ -456,3 +462,610 @@ async fn test_nonce() -> Result<(), Error> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_renew_installation() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let attribute = XmtpAttribute {
+            purpose: XmtpKeyPurpose::Installation,
+            encoding: KeyEncoding::Hex,
+        };
+        let value = b"000000000000000000000000000000000000000000000000000000000000000000";
+
+        // renewing an installation which was never granted must fail
+        match client
+            .renew_installation(
+                format!("0x{}", hex::encode(me.address())),
+                attribute.clone(),
+                value.to_vec(),
+                me.sign_attribute(
+                    &context.registry,
+                    attribute.clone().into(),
+                    value.to_vec(),
+                    U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+                )
+                .await?,
+            )
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), -31999);
+                assert_eq!(
+                    e.message(),
+                    "The installation has not been granted for this DID"
+                );
+            }
+            _ => panic!("renew_installation was expected to fail before a grant"),
+        };
+
+        client
+            .grant_installation(
+                format!("0x{}", hex::encode(me.address())),
+                attribute.clone(),
+                value.to_vec(),
+                me.sign_attribute(
+                    &context.registry,
+                    attribute.clone().into(),
+                    value.to_vec(),
+                    U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+                )
+                .await?,
+            )
+            .await?;
+
+        let res = client
+            .renew_installation(
+                format!("0x{}", hex::encode(me.address())),
+                attribute.clone(),
+                value.to_vec(),
+                me.sign_attribute(
+                    &context.registry,
+                    attribute.into(),
+                    value.to_vec(),
+                    U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+                )
+                .await?,
+            )
+            .await?;
+
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(&res.message, "Installation renewed.");
+        assert!(res.transaction.is_some());
+
+        let res = client
+            .fetch_key_packages(format!("0x{}", hex::encode(me.address())))
+            .await?;
+        // the renewed installation replaces the original grant
+        assert_eq!(res.installation, vec![hex::decode(value).unwrap()]);
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_list_expiring_installations() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let name = *b"xmtp/installation/hex           ";
+        let value = b"000000000000000000000000000000000000000000000000000000000000000000";
+        // set_attribute grants with a validity of one week
+        set_attribute(name, value.to_vec(), &me, &context.registry).await?;
+
+        let res = client
+            .list_expiring_installations(format!("0x{}", hex::encode(me.address())), 60)
+            .await?;
+        assert_eq!(res.status, Status::Success);
+        assert!(res.installations.is_empty());
+
+        let res = client
+            .list_expiring_installations(format!("0x{}", hex::encode(me.address())), 2 * 604_800)
+            .await?;
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(res.installations.len(), 1);
+        assert_eq!(res.installations[0].name, name);
+        assert_eq!(res.installations[0].value, value.to_vec());
+
+        // revoked installations are no longer reported
+        client
+            .revoke_installation(
+                format!("0x{}", hex::encode(me.address())),
+                XmtpAttribute {
+                    purpose: XmtpKeyPurpose::Installation,
+                    encoding: KeyEncoding::Hex,
+                },
+                value.to_vec(),
+                me.sign_revoke_attribute(&context.registry, name, value.to_vec())
+                    .await?,
+            )
+            .await?;
+
+        let res = client
+            .list_expiring_installations(format!("0x{}", hex::encode(me.address())), 2 * 604_800)
+            .await?;
+        assert!(res.installations.is_empty());
+
+        Ok(())
+    })
+    .await
+}
+
+/// Signs a batch operation over an explicit registry nonce
+fn sign_operation(
+    wallet: &LocalWallet,
+    registry: Address,
+    nonce: U256,
+    kind: InstallationOperationKind,
+    value: &[u8],
+) -> Result<InstallationOperation, Error> {
+    let name = XmtpAttribute {
+        purpose: XmtpKeyPurpose::Installation,
+        encoding: KeyEncoding::Hex,
+    };
+    let attribute: [u8; 32] = name.clone().into();
+    let digest = match kind {
+        InstallationOperationKind::Grant => set_attribute_digest(
+            registry,
+            nonce,
+            wallet.address(),
+            attribute,
+            value,
+            U256::from(DEFAULT_ATTRIBUTE_VALIDITY),
+        )?,
+        InstallationOperationKind::Revoke => {
+            revoke_attribute_digest(registry, nonce, wallet.address(), attribute, value)?
+        }
+    };
+
+    Ok(InstallationOperation {
+        kind,
+        name,
+        value: value.to_vec(),
+        signature: wallet.sign_hash(digest)?,
+    })
+}
+
+#[tokio::test]
+async fn test_batch_installations() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let old = b"000000000000000000000000000000000000000000000000000000000000000000";
+        let new = b"111111111111111111111111111111111111111111111111111111111111111111";
+        let other = b"222222222222222222222222222222222222222222222222222222222222222222";
+
+        let nonce = context.registry.nonce(me.address()).call().await?;
+        let registry = context.registry.address();
+        let operations = vec![
+            sign_operation(&me, registry, nonce, InstallationOperationKind::Grant, old)?,
+            sign_operation(
+                &me,
+                registry,
+                nonce + 1,
+                InstallationOperationKind::Grant,
+                new,
+            )?,
+            sign_operation(
+                &me,
+                registry,
+                nonce + 2,
+                InstallationOperationKind::Revoke,
+                old,
+            )?,
+            sign_operation(
+                &me,
+                registry,
+                nonce + 3,
+                InstallationOperationKind::Grant,
+                other,
+            )?,
+        ];
+
+        let res = client
+            .batch_installations(format!("0x{}", hex::encode(me.address())), operations)
+            .await?;
+
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(res.results.len(), 4);
+        for (index, result) in res.results.iter().enumerate() {
+            assert_eq!(result.index, index);
+            assert_eq!(result.status, Status::Success);
+            assert!(result.transaction.is_some());
+        }
+
+        let res = client
+            .fetch_key_packages(format!("0x{}", hex::encode(me.address())))
+            .await?;
+        assert_eq!(
+            res.installation,
+            vec![hex::decode(new).unwrap(), hex::decode(other).unwrap()]
+        );
+        assert_eq!(
+            context.registry.nonce(me.address()).call().await?,
+            nonce + 4
+        );
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_batch_installations_bad_signature() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let value = b"000000000000000000000000000000000000000000000000000000000000000000";
+
+        let nonce = context.registry.nonce(me.address()).call().await?;
+        let registry = context.registry.address();
+        // the second operation reuses the nonce of the first, so it can never be applied
+        let operations = vec![
+            sign_operation(
+                &me,
+                registry,
+                nonce,
+                InstallationOperationKind::Grant,
+                value,
+            )?,
+            sign_operation(
+                &me,
+                registry,
+                nonce,
+                InstallationOperationKind::Revoke,
+                value,
+            )?,
+        ];
+
+        match client
+            .batch_installations(format!("0x{}", hex::encode(me.address())), operations)
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), -31999);
+                assert_eq!(
+                    e.message(),
+                    "Signature of operation 1 does not match the DID owner"
+                );
+            }
+            _ => panic!("batch_installations was expected to reject the batch"),
+        };
+
+        // nothing was submitted
+        assert_eq!(context.registry.nonce(me.address()).call().await?, nonce);
+
+        match client
+            .batch_installations(format!("0x{}", hex::encode(me.address())), vec![])
+            .await
+        {
+            Err(ClientError::Call(e)) => assert_eq!(e.code(), -31999),
+            _ => panic!("batch_installations was expected to reject an empty batch"),
+        };
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_add_revoke_delegate() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let device: LocalWallet = anvil.keys()[4].clone().into();
+        let registry = context.registry.address();
+        let delegate_type: [u8; 32] = DelegateType::VeriKey.into();
+        let validity = 86_400;
+
+        let nonce = context.registry.nonce(me.address()).call().await?;
+        let signature = me.sign_hash(add_delegate_digest(
+            registry,
+            nonce,
+            me.address(),
+            delegate_type,
+            device.address(),
+            U256::from(validity),
+        )?)?;
+
+        let res = client
+            .add_delegate(
+                format!("0x{}", hex::encode(me.address())),
+                DelegateType::VeriKey,
+                device.address(),
+                validity,
+                signature,
+            )
+            .await?;
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(&res.message, "Delegate added.");
+        assert!(res.transaction.is_some());
+
+        assert!(
+            context
+                .registry
+                .valid_delegate(me.address(), delegate_type, device.address())
+                .call()
+                .await?
+        );
+
+        let nonce = context.registry.nonce(me.address()).call().await?;
+        let signature = me.sign_hash(revoke_delegate_digest(
+            registry,
+            nonce,
+            me.address(),
+            delegate_type,
+            device.address(),
+        )?)?;
+
+        let res = client
+            .revoke_delegate(
+                format!("0x{}", hex::encode(me.address())),
+                DelegateType::VeriKey,
+                device.address(),
+                signature,
+            )
+            .await?;
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(&res.message, "Delegate revoked.");
+
+        assert!(
+            !context
+                .registry
+                .valid_delegate(me.address(), delegate_type, device.address())
+                .call()
+                .await?
+        );
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_change_owner() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let new_wallet: LocalWallet = anvil.keys()[4].clone().into();
+
+        // a signature by someone other than the owner is rejected before submission
+        let signature = new_wallet
+            .sign_owner(&context.registry, new_wallet.address())
+            .await?;
+        match client
+            .change_owner(
+                format!("0x{}", hex::encode(me.address())),
+                new_wallet.address(),
+                signature,
+            )
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), -31999);
+                assert_eq!(
+                    e.message(),
+                    "Signature does not match the current owner of the DID"
+                );
+            }
+            _ => panic!("change_owner was expected to fail for a non-owner signature"),
+        };
+
+        let signature = me
+            .sign_owner(&context.registry, new_wallet.address())
+            .await?;
+        let res = client
+            .change_owner(
+                format!("0x{}", hex::encode(me.address())),
+                new_wallet.address(),
+                signature,
+            )
+            .await?;
+
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(&res.message, "Owner changed.");
+        assert!(res.transaction.is_some());
+        assert_eq!(
+            context.registry.identity_owner(me.address()).call().await?,
+            new_wallet.address()
+        );
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_deactivate_did() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let did = format!("0x{}", hex::encode(me.address()));
+        let null_address = Address::from_str(NULL_ADDRESS).unwrap();
+
+        let signature = me.sign_owner(&context.registry, null_address).await?;
+        match client.deactivate_did(did.clone(), signature, false).await {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), -32602);
+                assert_eq!(
+                    e.message(),
+                    "Deactivation is permanent and must be explicitly confirmed"
+                );
+            }
+            _ => panic!("deactivate_did was expected to require confirmation"),
+        };
+
+        // the unconfirmed request did not submit anything, so the signature is still valid
+        let res = client.deactivate_did(did.clone(), signature, true).await?;
+        assert_eq!(res.status, Status::Success);
+        assert_eq!(&res.message, "DID deactivated.");
+        assert!(res.transaction.is_some());
+
+        match client.fetch_key_packages(did).await {
+            Err(ClientError::Call(e)) => assert_eq!(
+                e.message(),
+                "The DID has been deactivated, and no longer valid"
+            ),
+            _ => panic!("fetch_key_packages was expected to fail for a deactivated DID"),
+        };
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_resolve_did() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let name = *b"xmtp/installation/hex           ";
+        let value = b"000000000000000000000000000000000000000000000000000000000000000000";
+        let did = format!("did:ethr:0x{}", hex::encode(me.address()));
+
+        set_attribute(name, value.to_vec(), &me, &context.registry).await?;
+        let granted_at = context.signer.get_block_number().await?;
+
+        client
+            .revoke_installation(
+                format!("0x{}", hex::encode(me.address())),
+                XmtpAttribute {
+                    purpose: XmtpKeyPurpose::Installation,
+                    encoding: KeyEncoding::Hex,
+                },
+                value.to_vec(),
+                me.sign_revoke_attribute(&context.registry, name, value.to_vec())
+                    .await?,
+            )
+            .await?;
+
+        let latest = client.resolve_did(did.clone(), None).await?;
+        assert!(!latest.metadata.deactivated);
+        assert_eq!(latest.document.verification_method.len(), 1);
+        let serialized = serde_json::to_value(&latest)?;
+        assert!(serialized.get("document").is_some());
+        assert!(serialized.get("metadata").is_some());
+
+        let historic = client
+            .resolve_did(
+                did.clone(),
+                Some(ResolveDidOptions {
+                    version_id: Some(granted_at.as_u64()),
+                    version_time: None,
+                }),
+            )
+            .await?;
+        assert_eq!(historic.document.verification_method.len(), 2);
+        assert_eq!(
+            historic.document.verification_method[1].id,
+            DidUrl::parse(format!(
+                "did:ethr:0x{}?meta=installation#xmtp-0",
+                hex::encode(me.address())
+            ))
+            .unwrap()
+        );
+
+        match client
+            .resolve_did(
+                did.clone(),
+                Some(ResolveDidOptions {
+                    version_id: Some(granted_at.as_u64()),
+                    version_time: Some("2024-01-01T00:00:00Z".to_string()),
+                }),
+            )
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), INVALID_PARAMS_CODE);
+                assert_eq!(
+                    e.message(),
+                    "Only one of versionId and versionTime may be specified"
+                );
+            }
+            _ => panic!("resolve_did was expected to reject conflicting versions"),
+        };
+
+        match client
+            .resolve_did(
+                did.clone(),
+                Some(ResolveDidOptions {
+                    version_id: None,
+                    version_time: Some("1969-07-20T20:17:40Z".to_string()),
+                }),
+            )
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), INVALID_PARAMS_CODE);
+                assert_eq!(e.message(), "versionTime is before 1970-01-01T00:00:00Z");
+            }
+            _ => panic!("resolve_did was expected to reject a versionTime before 1970"),
+        };
+
+        // anvil starts its chain at the current time
+        match client
+            .resolve_did(
+                did,
+                Some(ResolveDidOptions {
+                    version_id: None,
+                    version_time: Some("2000-01-01T00:00:00Z".to_string()),
+                }),
+            )
+            .await
+        {
+            Err(ClientError::Call(e)) => {
+                assert_eq!(e.code(), INVALID_PARAMS_CODE);
+                assert_eq!(
+                    e.message(),
+                    "versionTime is before the first block of the chain"
+                );
+            }
+            _ => panic!("resolve_did was expected to reject a versionTime before genesis"),
+        };
+
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_prepare_grant_revoke_installation() -> Result<(), Error> {
+    with_xps_client(None, None, |client, _, _, anvil| async move {
+        let me: LocalWallet = anvil.keys()[3].clone().into();
+        let did = format!("0x{}", hex::encode(me.address()));
+        let attribute = XmtpAttribute {
+            purpose: XmtpKeyPurpose::Installation,
+            encoding: KeyEncoding::Hex,
+        };
+        let value = b"000000000000000000000000000000000000000000000000000000000000000000";
+
+        let prepared = client
+            .prepare_grant_installation(did.clone(), attribute.clone(), value.to_vec())
+            .await?;
+        assert_eq!(prepared.nonce, U256::zero());
+        client
+            .grant_installation(
+                did.clone(),
+                attribute.clone(),
+                value.to_vec(),
+                me.sign_hash(prepared.digest)?,
+            )
+            .await?;
+
+        let res = client.fetch_key_packages(did.clone()).await?;
+        assert_eq!(res.installation, vec![hex::decode(value).unwrap()]);
+
+        let prepared = client
+            .prepare_revoke_installation(did.clone(), attribute.clone(), value.to_vec())
+            .await?;
+        assert_eq!(prepared.nonce, U256::one());
+        client
+            .revoke_installation(
+                did.clone(),
+                attribute,
+                value.to_vec(),
+                me.sign_hash(prepared.digest)?,
+            )
+            .await?;
+
+        let res = client.fetch_key_packages(did).await?;
+        assert!(res.installation.is_empty());
+
+        Ok(())
+    })
+    .await
+}
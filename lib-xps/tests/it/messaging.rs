@@ -87,3 +87,80 @@ async fn test_send_message_fail() -> Result<(), Error> {
     })
     .await
 }
+
+#[tokio::test]
+async fn test_message_nonce() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _resolver, anvil| async move {
+        let wallet: LocalWallet = anvil.keys()[3].clone().into();
+        let me = get_user(&anvil, 3).await;
+
+        let nonce = client.message_nonce(me.address()).await?;
+        assert_eq!(nonce, U256::zero());
+
+        let conversation_id = keccak256(b"conversation_id");
+        let payload = Bytes::from_static(b"payload");
+        let signature = wallet
+            .sign_xmtp_message(
+                &context.conversation,
+                conversation_id,
+                payload.clone(),
+                me.address(),
+            )
+            .await?;
+        client
+            .send_message(Message {
+                conversation_id,
+                payload,
+                identity: me.address(),
+                signature,
+            })
+            .await?;
+
+        let nonce = client.message_nonce(me.address()).await?;
+        assert_eq!(nonce, U256::one());
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_prepare_send_message() -> Result<(), Error> {
+    with_xps_client(None, None, |client, context, _resolver, anvil| async move {
+        let wallet: LocalWallet = anvil.keys()[3].clone().into();
+        let me = get_user(&anvil, 3).await;
+
+        let conversation_id = keccak256(b"conversation_id");
+        let payload = Bytes::from_static(b"payload");
+
+        let prepared = client
+            .prepare_send_message(conversation_id, payload.clone(), me.address())
+            .await?;
+        assert_eq!(prepared.nonce, U256::zero());
+
+        // the prepared digest must match the one signed by `sign_xmtp_message`
+        let signature = wallet.sign_hash(prepared.digest)?;
+        assert_eq!(
+            signature,
+            wallet
+                .sign_xmtp_message(
+                    &context.conversation,
+                    conversation_id,
+                    payload.clone(),
+                    me.address(),
+                )
+                .await?
+        );
+
+        let result = client
+            .send_message(Message {
+                conversation_id,
+                payload,
+                identity: me.address(),
+                signature,
+            })
+            .await?;
+        assert_eq!(result.status, Status::Success);
+        Ok(())
+    })
+    .await
+}
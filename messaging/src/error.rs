@@ -1,4 +1,5 @@
 use ethers::{
+    abi::EncodePackedError,
     contract::ContractError,
     providers::{Middleware, ProviderError},
 };
@@ -14,4 +15,6 @@ pub enum MessagingOperationError<M: Middleware> {
     ProviderError(#[from] ProviderError),
     #[error("Error converting from int: {0}")]
     IntConversion(#[from] TryFromIntError),
+    #[error(transparent)]
+    Encode(#[from] EncodePackedError),
 }
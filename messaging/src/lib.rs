@@ -2,16 +2,16 @@ pub mod error;
 
 use error::MessagingOperationError;
 use ethers::{
-    abi::{Address, Token},
+    abi::Address,
     contract::abigen,
-    core::abi::encode_packed,
     core::types::{Bytes, Signature},
     providers::Middleware,
     signers::LocalWallet,
-    types::H256,
-    utils::keccak256,
+    types::U256,
+};
+use xps_types::{
+    digest::send_message_digest, error::ExtSignerError, Message, OperationResult, PreparedDigest,
 };
-use xps_types::{error::ExtSignerError, Message, SendMessageResult, Status};
 
 abigen!(
     Conversation,
@@ -35,7 +35,7 @@ where
     pub async fn send_message(
         &self,
         m: Message,
-    ) -> Result<SendMessageResult, MessagingOperationError<M>> {
+    ) -> Result<OperationResult, MessagingOperationError<M>> {
         let transaction_receipt = self
             .contract
             .send_message_signed(
@@ -49,11 +49,30 @@ where
             .send()
             .await?
             .await?;
-        Ok(SendMessageResult {
-            status: Status::Success,
-            message: "Message sent.".to_string(),
-            transaction: transaction_receipt.unwrap().transaction_hash.to_string(),
-        })
+        Ok(OperationResult::from_receipt(
+            "Message sent.",
+            transaction_receipt,
+        ))
+    }
+
+    /// get the nonce for `identity` from [`Conversation`], needed to sign messages sent with
+    /// [`Conversation::send_message_signed`]
+    pub async fn nonce(&self, identity: Address) -> Result<U256, MessagingOperationError<M>> {
+        let nonce = self.contract.nonce(identity).call().await?;
+        Ok(nonce)
+    }
+
+    /// Builds the digest `identity` must sign to send `payload` to `conversation_id`, using the
+    /// current [`Conversation`] nonce of `identity`.
+    pub async fn prepare_send_message(
+        &self,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+        identity: Address,
+    ) -> Result<PreparedDigest, MessagingOperationError<M>> {
+        let nonce = self.nonce(identity).await?;
+        let digest = send_message_digest(conversation_id, &payload, identity, nonce)?;
+        Ok(PreparedDigest { digest, nonce })
     }
 }
 
@@ -80,19 +99,7 @@ impl ConversationSignerExt for LocalWallet {
         identity: Address,
     ) -> Result<Signature, ExtSignerError<M>> {
         let nonce = conversation.nonce(identity).call().await?;
-        let mut nonce_bytes = [0; 32];
-        nonce.to_big_endian(&mut nonce_bytes);
-        let tokens = vec![
-            Token::FixedBytes(vec![0x19]),
-            Token::FixedBytes(vec![0x0]),
-            Token::FixedBytes(conversation_id[0..32].to_vec()),
-            Token::Bytes(payload.to_vec()),
-            Token::Address(identity),
-            Token::Bytes(nonce_bytes[0..32].to_vec()),
-        ];
-
-        let encoded = encode_packed(tokens.as_slice())?;
-        let digest = H256(keccak256(encoded));
+        let digest = send_message_digest(conversation_id, &payload, identity, nonce)?;
         let signature = self.sign_hash(digest)?;
         Ok(signature)
     }
@@ -3,7 +3,8 @@
 use std::num::TryFromIntError;
 
 use ethers::{
-    contract::ContractError,
+    abi::EncodePackedError,
+    contract::{ContractError, MulticallError},
     providers::{Middleware, ProviderError},
 };
 use thiserror::Error;
@@ -22,6 +23,26 @@ pub enum ContactOperationError<M: Middleware> {
     ResolutionError(lib_didethresolver::error::ResolverError<M>, String),
     #[error("The DID has been deactivated, and no longer valid")]
     DIDDeactivated,
+    #[error("The installation has not been granted for this DID")]
+    InstallationNotFound,
+    #[error("Signature does not match the current owner of the DID")]
+    NotOwner,
+    #[error("A batch must contain at least one operation")]
+    EmptyBatch,
+    #[error("Signature of operation {0} does not match the DID owner")]
+    BadSignature(usize),
+    #[error(transparent)]
+    Multicall(#[from] MulticallError<M>),
+    #[error(transparent)]
+    Encode(#[from] EncodePackedError),
+    #[error("Only one of versionId and versionTime may be specified")]
+    ConflictingVersion,
+    #[error("Invalid versionTime: {0}")]
+    VersionTime(#[from] chrono::ParseError),
+    #[error("versionTime is before 1970-01-01T00:00:00Z")]
+    VersionTimeBeforeEpoch,
+    #[error("versionTime is before the first block of the chain")]
+    VersionTimeBeforeGenesis,
     #[error("Type failed to convert")]
     Type(#[from] lib_didethresolver::error::TypeError),
     #[error("Error parsing hex bytes: {0}")]
@@ -4,17 +4,34 @@ mod test;
 
 use std::str::FromStr;
 
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::DateTime;
 use error::ContactOperationError;
 use ethers::{
+    contract::{parse_log, ContractCall, ContractError, Multicall, MulticallVersion},
     providers::Middleware,
-    types::{Address, Bytes, Signature, H160, U256},
+    types::{
+        Address, BlockId, BlockNumber, Bytes, Signature, TransactionReceipt, H160, H256, U256, U64,
+    },
 };
 use lib_didethresolver::{
-    did_registry::DIDRegistry,
-    types::{VerificationMethodProperties, XmtpAttribute},
+    did_registry::{DIDRegistry, DIDRegistryEvents, DidattributeChangedFilter},
+    types::{
+        DidDocument, DidResolutionResult, KeyEncoding, VerificationMethodProperties, XmtpAttribute,
+        XmtpKeyPurpose,
+    },
     Resolver,
 };
-use xps_types::{GrantInstallationResult, KeyPackageResult, Status};
+use rustc_hex::FromHex;
+use xps_types::{
+    digest::{change_owner_digest, revoke_attribute_digest, set_attribute_digest},
+    BatchInstallationResult, BatchOperationResult, DelegateType, ExpiringInstallation,
+    ExpiringInstallationsResult, InstallationOperation, InstallationOperationKind,
+    KeyPackageResult, OperationResult, PreparedDigest, ResolveDidOptions, Status,
+};
+
+/// Method prefix of a fully qualified did:ethr DID
+const DID_ETHR_PREFIX: &str = "did:ethr:";
 
 pub struct ContactOperations<Middleware> {
     registry: DIDRegistry<Middleware>,
@@ -57,34 +74,41 @@ where
             return Err(ContactOperationError::DIDDeactivated);
         }
 
-        let document = resolution.document;
-
-        let properties = document
-            .verification_method
-            .into_iter()
-            .filter(|method| {
-                method
-                    .id
-                    .fragment()
-                    .map(|f| f.starts_with("xmtp-"))
-                    .unwrap_or(false)
-                    && method
-                        .id
-                        .contains_query("meta".into(), "installation".into())
-            })
-            .filter_map(|method| method.verification_properties)
-            .collect::<Vec<VerificationMethodProperties>>();
-
         Ok(KeyPackageResult {
             status: Status::Success,
             message: "Key packages retrieved".to_string(),
-            installation: properties
-                .into_iter()
-                .map(TryFrom::try_from)
-                .collect::<Result<_, _>>()?,
+            installation: installation_key_packages(resolution.document)?,
         })
     }
 
+    /// Resolves the full DID document, along with its metadata, using [`Resolver::resolve_did`].
+    ///
+    /// `did` may be given either as an address or as a `did:ethr:` DID.
+    pub async fn resolve_did(
+        &self,
+        did: String,
+        options: ResolveDidOptions,
+    ) -> Result<DidResolutionResult, ContactOperationError<M>> {
+        let address = Address::from_str(did.trim_start_matches(DID_ETHR_PREFIX))?;
+
+        let version = match (options.version_id, options.version_time) {
+            (Some(_), Some(_)) => return Err(ContactOperationError::ConflictingVersion),
+            (Some(version_id), None) => Some(U64::from(version_id)),
+            (None, Some(version_time)) => {
+                let time = DateTime::parse_from_rfc3339(&version_time)?;
+                let timestamp = u64::try_from(time.timestamp())
+                    .map_err(|_| ContactOperationError::VersionTimeBeforeEpoch)?;
+                Some(self.block_at_time(U256::from(timestamp)).await?)
+            }
+            (None, None) => None,
+        };
+
+        self.resolver
+            .resolve_did(address, version)
+            .await
+            .map_err(|e| ContactOperationError::ResolutionError(e, did))
+    }
+
     /// Grants an XMTP installation via the did:ethr registry.
     pub async fn grant_installation(
         &self,
@@ -93,7 +117,7 @@ where
         value: Vec<u8>,
         signature: Signature,
         validity: U256,
-    ) -> Result<GrantInstallationResult, ContactOperationError<M>> {
+    ) -> Result<OperationResult, ContactOperationError<M>> {
         let address = self.resolve_did_address(did)?;
         let attribute: [u8; 32] = name.into();
         log::debug!(
@@ -125,11 +149,10 @@ where
             );
         }
 
-        Ok(GrantInstallationResult {
-            status: Status::Success,
-            message: "Installation request complete.".to_string(),
-            transaction: transaction_receipt.map(|r| r.transaction_hash),
-        })
+        Ok(OperationResult::from_receipt(
+            "Installation request complete.",
+            transaction_receipt,
+        ))
     }
 
     /// Revokes an XMTP installation via the did:ethr registry.
@@ -139,7 +162,7 @@ where
         name: XmtpAttribute,
         value: Vec<u8>,
         signature: Signature,
-    ) -> Result<(), ContactOperationError<M>> {
+    ) -> Result<OperationResult, ContactOperationError<M>> {
         let address = self.resolve_did_address(did)?;
         let attribute: [u8; 32] = name.into();
         log::debug!(
@@ -170,7 +193,577 @@ where
             );
         }
 
-        Ok(())
+        Ok(OperationResult::from_receipt(
+            "Installation revoked.",
+            transaction_receipt,
+        ))
+    }
+
+    /// Renews an XMTP installation that was previously granted via the did:ethr registry.
+    ///
+    /// The installation must not have been revoked. A fresh signature over the same attribute
+    /// name and value is required, since the registry nonce advances with every change.
+    pub async fn renew_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+        validity: U256,
+    ) -> Result<OperationResult, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did.clone())?;
+        let attribute: [u8; 32] = name.clone().into();
+
+        let granted = self
+            .current_attributes(address)
+            .await?
+            .into_iter()
+            .any(|a| a.name == attribute && a.value.to_vec() == value);
+        if !granted {
+            return Err(ContactOperationError::InstallationNotFound);
+        }
+
+        let mut result = self
+            .grant_installation(did, name, value, signature, validity)
+            .await?;
+
+        if result.status == Status::Success {
+            result.message = "Installation renewed.".to_string();
+        }
+        Ok(result)
+    }
+
+    /// Lists the installations of a DID which expire within `within_seconds` of the latest block.
+    ///
+    /// The installations are those of the DID document, as for [`Self::fetch_key_packages`];
+    /// the registry change log only provides the validity of each.
+    pub async fn list_expiring_installations(
+        &self,
+        did: String,
+        within_seconds: u64,
+    ) -> Result<ExpiringInstallationsResult, ContactOperationError<M>> {
+        let address = Address::from_str(&did)?;
+
+        let resolution = self
+            .resolver
+            .resolve_did(address, None)
+            .await
+            .map_err(|e| ContactOperationError::ResolutionError(e, did))?;
+
+        if resolution.metadata.deactivated {
+            return Err(ContactOperationError::DIDDeactivated);
+        }
+
+        let key_packages = installation_key_packages(resolution.document)?;
+        let now = self.latest_timestamp().await?;
+        let deadline = now.saturating_add(U256::from(within_seconds));
+
+        let installations = self
+            .current_attributes(address)
+            .await?
+            .into_iter()
+            .filter(|a| a.valid_to <= deadline)
+            .filter(|a| {
+                installation_key_package(&a.name, &a.value)
+                    .is_some_and(|key_package| key_packages.iter().any(|k| **k == key_package))
+            })
+            .map(|a| ExpiringInstallation {
+                name: a.name,
+                value: a.value.to_vec(),
+                valid_to: a.valid_to,
+            })
+            .collect();
+
+        Ok(ExpiringInstallationsResult {
+            status: Status::Success,
+            message: "Expiring installations retrieved".to_string(),
+            installations,
+        })
+    }
+
+    /// Walks the registry change log of `address` from the most recent change backwards,
+    /// returning the latest event for every attribute that has not been revoked.
+    async fn current_attributes(
+        &self,
+        address: Address,
+    ) -> Result<Vec<DidattributeChangedFilter>, ContactOperationError<M>> {
+        let mut seen: Vec<DidattributeChangedFilter> = Vec::new();
+        let mut previous_change = self.registry.changed(address).call().await?;
+
+        while !previous_change.is_zero() {
+            let block = previous_change.as_u64();
+            let events = self
+                .registry
+                .events()
+                .from_block(block)
+                .to_block(block)
+                .topic1(H256::from(address))
+                .query()
+                .await?;
+
+            previous_change = U256::zero();
+            // events within a block are returned oldest first
+            for event in events.into_iter().rev() {
+                previous_change = match event {
+                    DIDRegistryEvents::DidattributeChangedFilter(attribute) => {
+                        let previous = attribute.previous_change;
+                        if !seen
+                            .iter()
+                            .any(|s| s.name == attribute.name && s.value == attribute.value)
+                        {
+                            seen.push(attribute);
+                        }
+                        previous
+                    }
+                    DIDRegistryEvents::DidownerChangedFilter(owner) => owner.previous_change,
+                    DIDRegistryEvents::DiddelegateChangedFilter(delegate) => {
+                        delegate.previous_change
+                    }
+                };
+            }
+        }
+
+        // a revoked attribute is recorded with a validity of zero
+        seen.retain(|a| !a.valid_to.is_zero());
+        Ok(seen)
+    }
+
+    /// Timestamp of the latest block, used as the reference point for attribute validity
+    async fn latest_timestamp(&self) -> Result<U256, ContactOperationError<M>> {
+        self.block_timestamp(BlockNumber::Latest).await
+    }
+
+    /// Timestamp of the given block
+    async fn block_timestamp<B: Into<BlockId> + Send + Sync>(
+        &self,
+        block: B,
+    ) -> Result<U256, ContactOperationError<M>> {
+        let block = self
+            .registry
+            .client()
+            .get_block(block)
+            .await
+            .map_err(ContractError::from_middleware_error)?;
+
+        Ok(block.map(|b| b.timestamp).unwrap_or_default())
+    }
+
+    /// Finds the number of the last block with a timestamp at or before `timestamp`, by binary
+    /// search over the chain.
+    async fn block_at_time(&self, timestamp: U256) -> Result<U64, ContactOperationError<M>> {
+        let latest = self
+            .registry
+            .client()
+            .get_block_number()
+            .await
+            .map_err(ContractError::from_middleware_error)?
+            .as_u64();
+
+        if self.block_timestamp(latest).await? <= timestamp {
+            return Ok(latest.into());
+        }
+        if self.block_timestamp(0u64).await? > timestamp {
+            return Err(ContactOperationError::VersionTimeBeforeGenesis);
+        }
+
+        // invariant: block `low` is at or before `timestamp`, block `high + 1` is after it
+        let (mut low, mut high) = (0, latest - 1);
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if self.block_timestamp(mid).await? <= timestamp {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(low.into())
+    }
+
+    /// Applies a batch of signed installation grants and revocations for a single DID.
+    ///
+    /// Every signature is verified against the current owner of the DID before anything is
+    /// submitted. If the chain has a known Multicall3 deployment the batch is submitted as a
+    /// single transaction, otherwise operations are submitted one after another in nonce order.
+    /// Once an operation fails, the following operations are skipped, since their signatures
+    /// were made over a registry nonce which will no longer be reached.
+    pub async fn batch_installations(
+        &self,
+        did: String,
+        operations: Vec<InstallationOperation>,
+        validity: U256,
+    ) -> Result<BatchInstallationResult, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+        if operations.is_empty() {
+            return Err(ContactOperationError::EmptyBatch);
+        }
+
+        let owner = self.registry.identity_owner(address).call().await?;
+        let nonce = self.registry.nonce(owner).call().await?;
+
+        for (index, operation) in operations.iter().enumerate() {
+            let attribute: [u8; 32] = operation.name.clone().into();
+            let operation_nonce = nonce + U256::from(index);
+            let digest = match operation.kind {
+                InstallationOperationKind::Grant => set_attribute_digest(
+                    self.registry.address(),
+                    operation_nonce,
+                    address,
+                    attribute,
+                    &operation.value,
+                    validity,
+                )?,
+                InstallationOperationKind::Revoke => revoke_attribute_digest(
+                    self.registry.address(),
+                    operation_nonce,
+                    address,
+                    attribute,
+                    &operation.value,
+                )?,
+            };
+            operation
+                .signature
+                .verify(digest, owner)
+                .map_err(|_| ContactOperationError::BadSignature(index))?;
+        }
+
+        let calls = operations
+            .iter()
+            .map(|operation| self.operation_call(address, operation, validity))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let results = match Multicall::<M>::new(self.registry.client(), None).await {
+            Ok(multicall) => {
+                self.submit_multicall(multicall, calls, address, &operations)
+                    .await?
+            }
+            Err(e) => {
+                log::debug!("multicall unavailable, submitting sequentially: {}", e);
+                Self::submit_sequential(calls).await
+            }
+        };
+
+        let failed = results
+            .iter()
+            .filter(|r| r.status == Status::Failed)
+            .count();
+
+        Ok(BatchInstallationResult {
+            status: if failed == 0 {
+                Status::Success
+            } else {
+                Status::Failed
+            },
+            message: format!(
+                "{} of {} operations applied.",
+                results.len() - failed,
+                results.len()
+            ),
+            results,
+        })
+    }
+
+    /// Builds the registry call for a single operation of a batch
+    fn operation_call(
+        &self,
+        address: Address,
+        operation: &InstallationOperation,
+        validity: U256,
+    ) -> Result<ContractCall<M, ()>, ContactOperationError<M>> {
+        let attribute: [u8; 32] = operation.name.clone().into();
+        let signature = operation.signature;
+
+        let call = match operation.kind {
+            InstallationOperationKind::Grant => self.registry.set_attribute_signed(
+                address,
+                signature.v.try_into()?,
+                signature.r.into(),
+                signature.s.into(),
+                attribute,
+                operation.value.clone().into(),
+                validity,
+            ),
+            InstallationOperationKind::Revoke => self.registry.revoke_attribute_signed(
+                address,
+                signature.v.try_into()?,
+                signature.r.into(),
+                signature.s.into(),
+                attribute,
+                operation.value.clone().into(),
+            ),
+        };
+        Ok(call)
+    }
+
+    /// Submits all calls in a single Multicall3 transaction. The batch is simulated first, so it
+    /// is not sent when every call would revert. Calls are allowed to fail without reverting the
+    /// batch, so the outcome of each is read from the logs of the mined transaction, see
+    /// [`batch_results`]. If the batch itself fails, every operation is reported as failed.
+    async fn submit_multicall(
+        &self,
+        multicall: Multicall<M>,
+        calls: Vec<ContractCall<M, ()>>,
+        address: Address,
+        operations: &[InstallationOperation],
+    ) -> Result<Vec<BatchOperationResult>, ContactOperationError<M>> {
+        let mut multicall = multicall.version(MulticallVersion::Multicall3);
+        for call in calls {
+            multicall.add_call(call, true);
+        }
+
+        let simulated = multicall.call_raw().await?;
+        if !simulated.iter().any(Result::is_ok) {
+            return Ok(all_failed(operations.len(), "Operation reverted.", None));
+        }
+
+        let outcome = match multicall.send().await {
+            Ok(pending) => pending.await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        Ok(match outcome {
+            Ok(Some(receipt)) if receipt.status == Some(1.into()) => {
+                batch_results(self.registry.address(), address, operations, &receipt)
+            }
+            Ok(Some(receipt)) => all_failed(
+                operations.len(),
+                "Batch transaction reverted.",
+                Some(receipt.transaction_hash),
+            ),
+            Ok(None) => all_failed(
+                operations.len(),
+                "Transaction dropped from the mempool.",
+                None,
+            ),
+            Err(e) => all_failed(operations.len(), &e, None),
+        })
+    }
+
+    /// Submits calls one at a time, waiting for each to be mined before sending the next.
+    async fn submit_sequential(calls: Vec<ContractCall<M, ()>>) -> Vec<BatchOperationResult> {
+        let mut results = Vec::with_capacity(calls.len());
+        let mut failed = false;
+
+        for (index, call) in calls.iter().enumerate() {
+            if failed {
+                results.push(BatchOperationResult {
+                    index,
+                    status: Status::Failed,
+                    message: "Skipped after an earlier operation failed.".to_string(),
+                    transaction: None,
+                });
+                continue;
+            }
+
+            let outcome = match call.send().await {
+                Ok(pending) => pending.await.map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let result = match outcome {
+                Ok(Some(receipt)) if receipt.status == Some(1.into()) => BatchOperationResult {
+                    index,
+                    status: Status::Success,
+                    message: "Operation applied.".to_string(),
+                    transaction: Some(receipt.transaction_hash),
+                },
+                Ok(Some(receipt)) => BatchOperationResult {
+                    index,
+                    status: Status::Failed,
+                    message: "Operation reverted.".to_string(),
+                    transaction: Some(receipt.transaction_hash),
+                },
+                Ok(None) => BatchOperationResult {
+                    index,
+                    status: Status::Failed,
+                    message: "Transaction dropped from the mempool.".to_string(),
+                    transaction: None,
+                },
+                Err(e) => BatchOperationResult {
+                    index,
+                    status: Status::Failed,
+                    message: e,
+                    transaction: None,
+                },
+            };
+            failed = result.status == Status::Failed;
+            results.push(result);
+        }
+        results
+    }
+
+    /// Adds a delegate to a DID via the did:ethr registry, valid for `validity` seconds.
+    pub async fn add_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+        validity: U256,
+    ) -> Result<OperationResult, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+        log::debug!(
+            "adding delegate {:?} of type {}",
+            delegate,
+            delegate_type.as_str()
+        );
+
+        let transaction_receipt = self
+            .registry
+            .add_delegate_signed(
+                address,
+                signature.v.try_into()?,
+                signature.r.into(),
+                signature.s.into(),
+                delegate_type.into(),
+                delegate,
+                validity,
+            )
+            .send()
+            .await?
+            .await?;
+
+        Ok(OperationResult::from_receipt(
+            "Delegate added.",
+            transaction_receipt,
+        ))
+    }
+
+    /// Revokes a delegate of a DID via the did:ethr registry.
+    pub async fn revoke_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+        log::debug!(
+            "revoking delegate {:?} of type {}",
+            delegate,
+            delegate_type.as_str()
+        );
+
+        let transaction_receipt = self
+            .registry
+            .revoke_delegate_signed(
+                address,
+                signature.v.try_into()?,
+                signature.r.into(),
+                signature.s.into(),
+                delegate_type.into(),
+                delegate,
+            )
+            .send()
+            .await?
+            .await?;
+
+        Ok(OperationResult::from_receipt(
+            "Delegate revoked.",
+            transaction_receipt,
+        ))
+    }
+
+    /// Transfers ownership of a DID to `new_owner` via the did:ethr registry.
+    ///
+    /// The signature is verified against the current owner of the DID before the transaction
+    /// is submitted, so that a bad signature does not cost the gateway any gas. A deactivated
+    /// DID is owned by the null address, and can not change owner again.
+    pub async fn change_owner(
+        &self,
+        did: String,
+        new_owner: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+
+        let owner = self.registry.identity_owner(address).call().await?;
+        if owner.is_zero() {
+            return Err(ContactOperationError::DIDDeactivated);
+        }
+        let nonce = self.registry.nonce(owner).call().await?;
+        let digest = change_owner_digest(self.registry.address(), nonce, address, new_owner)?;
+        signature
+            .verify(digest, owner)
+            .map_err(|_| ContactOperationError::NotOwner)?;
+
+        log::debug!("changing owner of {:?} to {:?}", address, new_owner);
+
+        let transaction_receipt = self
+            .registry
+            .change_owner_signed(
+                address,
+                signature.v.try_into()?,
+                signature.r.into(),
+                signature.s.into(),
+                new_owner,
+            )
+            .send()
+            .await?
+            .await?;
+
+        Ok(OperationResult::from_receipt(
+            "Owner changed.",
+            transaction_receipt,
+        ))
+    }
+
+    /// Deactivates a DID by transferring its ownership to the null address. This can not be
+    /// undone.
+    pub async fn deactivate_did(
+        &self,
+        did: String,
+        signature: Signature,
+    ) -> Result<OperationResult, ContactOperationError<M>> {
+        let mut result = self.change_owner(did, Address::zero(), signature).await?;
+
+        if result.status == Status::Success {
+            result.message = "DID deactivated.".to_string();
+        }
+        Ok(result)
+    }
+
+    /// Builds the digest the owner of `did` must sign to grant an installation with
+    /// [`ContactOperations::grant_installation`] and the given `validity`.
+    pub async fn prepare_grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        validity: U256,
+    ) -> Result<PreparedDigest, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+        let nonce = self.owner_nonce(address).await?;
+        let digest = set_attribute_digest(
+            self.registry.address(),
+            nonce,
+            address,
+            name.into(),
+            &value,
+            validity,
+        )?;
+        Ok(PreparedDigest { digest, nonce })
+    }
+
+    /// Builds the digest the owner of `did` must sign to revoke an installation with
+    /// [`ContactOperations::revoke_installation`].
+    pub async fn prepare_revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ContactOperationError<M>> {
+        let address = self.resolve_did_address(did)?;
+        let nonce = self.owner_nonce(address).await?;
+        let digest =
+            revoke_attribute_digest(self.registry.address(), nonce, address, name.into(), &value)?;
+        Ok(PreparedDigest { digest, nonce })
+    }
+
+    /// The registry nonce used in signed operations on `address`, which is the nonce of its
+    /// current owner
+    async fn owner_nonce(&self, address: Address) -> Result<U256, ContactOperationError<M>> {
+        let owner = self.registry.identity_owner(address).call().await?;
+        let nonce = self.registry.nonce(owner).call().await?;
+        Ok(nonce)
     }
 
     /// get the nonce for a given address from [`DIDRegistry`]
@@ -181,12 +774,118 @@ where
     }
 }
 
+/// Key packages of the installation verification methods of a resolved DID document
+fn installation_key_packages<M: Middleware>(
+    document: DidDocument,
+) -> Result<Vec<Bytes>, ContactOperationError<M>> {
+    document
+        .verification_method
+        .into_iter()
+        .filter(|method| {
+            method
+                .id
+                .fragment()
+                .map(|f| f.starts_with("xmtp-"))
+                .unwrap_or(false)
+                && method
+                    .id
+                    .contains_query("meta".into(), "installation".into())
+        })
+        .filter_map(|method| method.verification_properties)
+        .map(|properties: VerificationMethodProperties| Ok(properties.try_into()?))
+        .collect()
+}
+
+/// The key package held by an installation attribute, decoded with the encoding named by the
+/// attribute as the resolver decodes it. `None` if `name` is not an installation attribute.
+fn installation_key_package(name: &[u8; 32], value: &[u8]) -> Option<Vec<u8>> {
+    let encoded = std::str::from_utf8(value).ok()?;
+    let decoders: [(KeyEncoding, fn(&str) -> Option<Vec<u8>>); 3] = [
+        (KeyEncoding::Hex, |s| s.from_hex().ok()),
+        (KeyEncoding::Base64, |s| STANDARD.decode(s).ok()),
+        (KeyEncoding::Base58, |s| bs58::decode(s).into_vec().ok()),
+    ];
+    decoders.into_iter().find_map(|(encoding, decode)| {
+        let attribute: [u8; 32] = XmtpAttribute {
+            purpose: XmtpKeyPurpose::Installation,
+            encoding,
+        }
+        .into();
+        (attribute == *name).then(|| decode(encoded)).flatten()
+    })
+}
+
+/// Results of the `operations` of a batch mined in `receipt`, for the DID of `identity`. The
+/// calls which failed within the batch left no log, and the operations applied form a prefix of
+/// the batch, since each signature was made over the registry nonce left by the previous
+/// operation: an operation was applied if the next attribute change logged by `registry` is the
+/// one it makes.
+fn batch_results(
+    registry: Address,
+    identity: Address,
+    operations: &[InstallationOperation],
+    receipt: &TransactionReceipt,
+) -> Vec<BatchOperationResult> {
+    let mut changes = receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == registry)
+        .filter_map(|log| parse_log::<DidattributeChangedFilter>(log.clone()).ok())
+        .filter(|change| change.identity == identity)
+        .peekable();
+
+    operations
+        .iter()
+        .enumerate()
+        .map(|(index, operation)| {
+            let attribute: [u8; 32] = operation.name.clone().into();
+            // a revocation is recorded with a validity of zero
+            let revoked = operation.kind == InstallationOperationKind::Revoke;
+            let applied = changes
+                .next_if(|change| {
+                    change.name == attribute
+                        && change.value == operation.value
+                        && change.valid_to.is_zero() == revoked
+                })
+                .is_some();
+            BatchOperationResult {
+                index,
+                status: if applied {
+                    Status::Success
+                } else {
+                    Status::Failed
+                },
+                message: if applied {
+                    "Operation applied.".to_string()
+                } else {
+                    "Operation reverted.".to_string()
+                },
+                transaction: Some(receipt.transaction_hash),
+            }
+        })
+        .collect()
+}
+
+/// A failed result for each of the `count` operations of a batch
+fn all_failed(count: usize, message: &str, transaction: Option<H256>) -> Vec<BatchOperationResult> {
+    (0..count)
+        .map(|index| BatchOperationResult {
+            index,
+            status: Status::Failed,
+            message: message.to_string(),
+            transaction,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ethers::{
-        abi::AbiEncode,
+        abi::{self, AbiEncode, Token},
+        contract::EthEvent,
         providers::{MockProvider, Provider},
+        types::Log,
     };
     use lib_didethresolver::did_registry::NonceReturn;
 
@@ -215,6 +914,39 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_resolve_did_options() {
+        let (ops, _) = ContactOperations::mocked();
+        let did = "did:ethr:0x1111111111111111111111111111111111111111".to_string();
+
+        let conflicting = ResolveDidOptions {
+            version_id: Some(1),
+            version_time: Some("2024-01-01T00:00:00Z".to_string()),
+        };
+        assert!(matches!(
+            ops.resolve_did(did.clone(), conflicting).await,
+            Err(ContactOperationError::ConflictingVersion)
+        ));
+
+        let malformed = ResolveDidOptions {
+            version_id: None,
+            version_time: Some("yesterday".to_string()),
+        };
+        assert!(matches!(
+            ops.resolve_did(did.clone(), malformed).await,
+            Err(ContactOperationError::VersionTime(_))
+        ));
+
+        let before_epoch = ResolveDidOptions {
+            version_id: None,
+            version_time: Some("1969-12-31T23:59:59Z".to_string()),
+        };
+        assert!(matches!(
+            ops.resolve_did(did, before_epoch).await,
+            Err(ContactOperationError::VersionTimeBeforeEpoch)
+        ));
+    }
+
     #[tokio::test]
     async fn test_nonce() {
         let (ops, mock) = ContactOperations::mocked();
@@ -229,4 +961,127 @@ mod tests {
 
         assert_eq!(nonce, U256::from(212));
     }
+
+    fn operation(kind: InstallationOperationKind, value: &[u8]) -> InstallationOperation {
+        InstallationOperation {
+            kind,
+            name: XmtpAttribute {
+                purpose: XmtpKeyPurpose::Installation,
+                encoding: KeyEncoding::Hex,
+            },
+            value: value.to_vec().into(),
+            signature: Signature {
+                r: U256::zero(),
+                s: U256::zero(),
+                v: 27,
+            },
+        }
+    }
+
+    /// The log of the registry at `registry` changing an attribute of `identity`
+    fn attribute_changed(
+        registry: Address,
+        identity: Address,
+        operation: &InstallationOperation,
+    ) -> Log {
+        let name: [u8; 32] = operation.name.clone().into();
+        let valid_to = match operation.kind {
+            InstallationOperationKind::Grant => U256::from(u64::MAX),
+            InstallationOperationKind::Revoke => U256::zero(),
+        };
+        Log {
+            address: registry,
+            topics: vec![DidattributeChangedFilter::signature(), identity.into()],
+            data: abi::encode(&[
+                Token::FixedBytes(name.to_vec()),
+                Token::Bytes(operation.value.to_vec()),
+                Token::Uint(valid_to),
+                Token::Uint(U256::zero()),
+            ])
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_batch_results() {
+        let registry = Address::repeat_byte(1);
+        let identity = Address::repeat_byte(2);
+        let operations = vec![
+            operation(InstallationOperationKind::Grant, b"aa"),
+            operation(InstallationOperationKind::Revoke, b"bb"),
+            operation(InstallationOperationKind::Grant, b"cc"),
+        ];
+        let hash = H256::repeat_byte(3);
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            status: Some(1.into()),
+            logs: vec![
+                attribute_changed(registry, identity, &operations[0]),
+                // changes of other identities or contracts are ignored
+                attribute_changed(registry, Address::repeat_byte(4), &operations[1]),
+                attribute_changed(Address::repeat_byte(5), identity, &operations[1]),
+                attribute_changed(registry, identity, &operations[1]),
+            ],
+            ..Default::default()
+        };
+
+        let results = batch_results(registry, identity, &operations, &receipt);
+        let statuses: Vec<_> = results.iter().map(|r| r.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![Status::Success, Status::Success, Status::Failed]
+        );
+        assert!(results
+            .iter()
+            .enumerate()
+            .all(|(index, r)| r.index == index && r.transaction == Some(hash)));
+        assert_eq!(results[2].message, "Operation reverted.");
+
+        // an operation whose change was not logged is not applied, even if a later one was
+        let receipt = TransactionReceipt {
+            logs: vec![attribute_changed(registry, identity, &operations[1])],
+            ..receipt
+        };
+        let results = batch_results(registry, identity, &operations[..1], &receipt);
+        assert_eq!(results[0].status, Status::Failed);
+    }
+
+    #[test]
+    fn test_installation_key_package() {
+        let name = |encoding| -> [u8; 32] {
+            XmtpAttribute {
+                purpose: XmtpKeyPurpose::Installation,
+                encoding,
+            }
+            .into()
+        };
+        assert_eq!(
+            installation_key_package(&name(KeyEncoding::Hex), b"00ff"),
+            Some(vec![0, 255])
+        );
+        assert_eq!(
+            installation_key_package(&name(KeyEncoding::Base64), b"AP8="),
+            Some(vec![0, 255])
+        );
+        assert_eq!(
+            installation_key_package(&name(KeyEncoding::Base58), b"15Q"),
+            Some(vec![0, 255])
+        );
+        assert_eq!(
+            installation_key_package(&name(KeyEncoding::Hex), b"not hex"),
+            None
+        );
+        assert_eq!(installation_key_package(&[0; 32], b"00ff"), None);
+    }
+
+    #[test]
+    fn test_all_failed() {
+        let results = all_failed(2, "Batch transaction reverted.", None);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.status == Status::Failed
+            && r.message == "Batch transaction reverted."
+            && r.transaction.is_none()));
+        assert_eq!(results[1].index, 1);
+    }
 }
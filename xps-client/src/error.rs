@@ -0,0 +1,68 @@
+//! Error variants for the XPS client
+
+use ethers::signers::WalletError;
+use jsonrpsee::core::ClientError;
+use thiserror::Error;
+
+/// Message returned by the gateway when a DID has been deactivated
+const DID_DEACTIVATED: &str = "The DID has been deactivated, and no longer valid";
+
+#[derive(Error, Debug)]
+pub enum GatewayError {
+    /// The gateway could not be reached, even after retrying
+    #[error("Gateway unreachable after {attempts} attempts: {source}")]
+    Unreachable {
+        attempts: u32,
+        #[source]
+        source: ClientError,
+    },
+    /// A request changing state failed after it was sent, so the gateway may or may not have
+    /// applied it. Check the nonce or the transaction receipt before sending it again.
+    #[error("Outcome of the request unknown: {0}")]
+    Indeterminate(ClientError),
+    /// The connection to the gateway was closed and the client must be rebuilt
+    #[error("Connection to the gateway was closed: {0}")]
+    Disconnected(ClientError),
+    /// The DID has been deactivated
+    #[error("The DID has been deactivated")]
+    DIDDeactivated,
+    /// The gateway rejected the request
+    #[error("Gateway rejected the request ({code}): {message}")]
+    Rejected { code: i32, message: String },
+    /// The gateway accepted the request, but reported the operation as failed
+    #[error("Operation failed: {0}")]
+    Failed(String),
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    #[error(transparent)]
+    Client(ClientError),
+}
+
+impl GatewayError {
+    /// Whether the request may succeed if it is sent again
+    pub(crate) fn is_transient(error: &ClientError) -> bool {
+        matches!(
+            error,
+            ClientError::Transport(_) | ClientError::RequestTimeout | ClientError::MaxSlotsExceeded
+        )
+    }
+
+    /// Whether the request failed before being sent to the gateway
+    pub(crate) fn is_unsent(error: &ClientError) -> bool {
+        matches!(error, ClientError::MaxSlotsExceeded)
+    }
+}
+
+impl From<ClientError> for GatewayError {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::Call(e) if e.message() == DID_DEACTIVATED => GatewayError::DIDDeactivated,
+            ClientError::Call(e) => GatewayError::Rejected {
+                code: e.code(),
+                message: e.message().to_string(),
+            },
+            e @ ClientError::RestartNeeded(_) => GatewayError::Disconnected(e),
+            e => GatewayError::Client(e),
+        }
+    }
+}
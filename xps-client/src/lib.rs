@@ -0,0 +1,544 @@
+//! High-level client for the XMTP Postal Service (XPS) gateway.
+//!
+//! [`Gateway`] wraps the raw [`XpsClient`] interface: it fetches nonces and digests from the
+//! gateway, signs them with the caller's wallet, retries reads failing with a transient error
+//! and turns failures into a typed [`GatewayError`].
+
+pub mod error;
+
+use std::{future::Future, time::Duration};
+
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes},
+};
+use jsonrpsee::{
+    core::{client::ClientT, ClientError},
+    ws_client::{WsClient, WsClientBuilder},
+};
+use lib_didethresolver::types::{KeyEncoding, XmtpAttribute, XmtpKeyPurpose};
+use xps_types::{
+    rpc::{self, XpsClient},
+    GrantInstallationResult, Message, OperationResult, SendMessageResult, Status,
+};
+
+pub use error::GatewayError;
+
+/// Retry behaviour for requests failing with a transient error, such as a timeout.
+///
+/// Requests changing state are only retried when they failed before being sent, since the
+/// gateway may otherwise have relayed them before the error. Those fail with
+/// [`GatewayError::Indeterminate`] instead: check the nonce or the transaction receipt before
+/// sending them again.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub attempts: u32,
+    /// Delay before the first retry, doubled after every attempt
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// A connection to an XPS gateway
+pub struct Gateway<C> {
+    client: C,
+    retry: RetryPolicy,
+}
+
+impl Gateway<WsClient> {
+    /// Connects to the gateway at `url` over a websocket
+    pub async fn connect(url: &str) -> Result<Self, GatewayError> {
+        let client = WsClientBuilder::default().build(url).await?;
+        Ok(Self::new(client))
+    }
+}
+
+impl<C> Gateway<C>
+where
+    C: ClientT + Send + Sync,
+{
+    /// Creates a new Gateway from an existing JSON-RPC client
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Replaces the default [`RetryPolicy`]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The underlying JSON-RPC client, for methods not wrapped by [`Gateway`]
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Registers `key_package` as an installation of the DID owned by `wallet`
+    pub async fn register_installation(
+        &self,
+        wallet: &LocalWallet,
+        key_package: &[u8],
+    ) -> Result<GrantInstallationResult, GatewayError> {
+        let did = did_of(wallet);
+        let value = installation_value(key_package);
+
+        let prepared = self
+            .retry(|| {
+                self.client.prepare_grant_installation(
+                    did.clone(),
+                    installation_attribute(),
+                    value.clone(),
+                )
+            })
+            .await?;
+        let signature = wallet.sign_hash(prepared.digest)?;
+
+        let result = self
+            .retry_unsent(|| {
+                self.client.grant_installation(
+                    did.clone(),
+                    installation_attribute(),
+                    value.clone(),
+                    signature,
+                )
+            })
+            .await?;
+
+        check_status(&result.status, &result.message)?;
+        Ok(result)
+    }
+
+    /// Revokes the installation `key_package` from the DID owned by `wallet`
+    pub async fn revoke_installation(
+        &self,
+        wallet: &LocalWallet,
+        key_package: &[u8],
+    ) -> Result<OperationResult, GatewayError> {
+        let did = did_of(wallet);
+        let value = installation_value(key_package);
+
+        let prepared = self
+            .retry(|| {
+                self.client.prepare_revoke_installation(
+                    did.clone(),
+                    installation_attribute(),
+                    value.clone(),
+                )
+            })
+            .await?;
+        let signature = wallet.sign_hash(prepared.digest)?;
+
+        let result = self
+            .retry_unsent(|| {
+                rpc::XpsV2Client::revoke_installation(
+                    &self.client,
+                    did.clone(),
+                    installation_attribute(),
+                    value.clone(),
+                    signature,
+                )
+            })
+            .await?;
+
+        check_status(&result.status, &result.message)?;
+        Ok(result)
+    }
+
+    /// Sends `payload` to the conversation `conversation_id`, signed by `wallet`
+    pub async fn send(
+        &self,
+        wallet: &LocalWallet,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+    ) -> Result<SendMessageResult, GatewayError> {
+        let identity = wallet.address();
+
+        let prepared = self
+            .retry(|| {
+                self.client
+                    .prepare_send_message(conversation_id, payload.clone(), identity)
+            })
+            .await?;
+        let signature = wallet.sign_hash(prepared.digest)?;
+
+        let result = self
+            .retry_unsent(|| {
+                self.client.send_message(Message {
+                    conversation_id,
+                    payload: payload.clone(),
+                    identity,
+                    signature,
+                })
+            })
+            .await?;
+
+        check_status(&result.status, &result.message)?;
+        Ok(result)
+    }
+
+    /// Fetches the key packages of every installation registered for `did`
+    pub async fn installations(&self, did: Address) -> Result<Vec<Vec<u8>>, GatewayError> {
+        let did = format!("0x{}", hex::encode(did));
+
+        let result = self
+            .retry(|| self.client.fetch_key_packages(did.clone()))
+            .await?;
+
+        check_status(&result.status, &result.message)?;
+        Ok(result.installation)
+    }
+
+    /// Sends a request built by `request`, retrying according to the [`RetryPolicy`] while it
+    /// fails with a transient error
+    async fn retry<T, F, Fut>(&self, request: F) -> Result<T, GatewayError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        self.retry_while(request, GatewayError::is_transient).await
+    }
+
+    /// Sends a request changing state built by `request`, retrying according to the
+    /// [`RetryPolicy`] only while it fails before being sent
+    async fn retry_unsent<T, F, Fut>(&self, request: F) -> Result<T, GatewayError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        match self.retry_while(request, GatewayError::is_unsent).await {
+            Err(GatewayError::Client(e)) if GatewayError::is_transient(&e) => {
+                Err(GatewayError::Indeterminate(e))
+            }
+            result => result,
+        }
+    }
+
+    async fn retry_while<T, F, Fut>(
+        &self,
+        mut request: F,
+        retryable: fn(&ClientError) -> bool,
+    ) -> Result<T, GatewayError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut attempt = 1;
+        let mut backoff = self.retry.backoff;
+        loop {
+            match request().await {
+                Ok(value) => return Ok(value),
+                Err(e) if retryable(&e) => {
+                    if attempt >= self.retry.attempts {
+                        return Err(GatewayError::Unreachable {
+                            attempts: attempt,
+                            source: e,
+                        });
+                    }
+                    log::debug!(
+                        "attempt {} failed, retrying in {:?}: {}",
+                        attempt,
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// The DID of the identity owned by `wallet`
+fn did_of(wallet: &LocalWallet) -> String {
+    format!("0x{}", hex::encode(wallet.address()))
+}
+
+/// Installations are registered with their key package hex encoded
+fn installation_attribute() -> XmtpAttribute {
+    XmtpAttribute {
+        purpose: XmtpKeyPurpose::Installation,
+        encoding: KeyEncoding::Hex,
+    }
+}
+
+fn installation_value(key_package: &[u8]) -> Vec<u8> {
+    hex::encode(key_package).into_bytes()
+}
+
+fn check_status(status: &Status, message: &str) -> Result<(), GatewayError> {
+    match status {
+        Status::Success => Ok(()),
+        Status::Failed => Err(GatewayError::Failed(message.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        signers::LocalWallet,
+        types::{Signature, H256, U256},
+    };
+    use jsonrpsee::{
+        server::{Server, ServerHandle},
+        types::ErrorObjectOwned,
+        RpcModule,
+    };
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+    use xps_types::{KeyPackageResult, PreparedDigest};
+
+    const DIGEST: H256 = H256([7; 32]);
+
+    /// Starts a gateway stub answering with canned responses
+    async fn stub_gateway() -> (Gateway<WsClient>, ServerHandle) {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("xps_prepareGrantInstallation", |_, _| PreparedDigest {
+                digest: DIGEST,
+                nonce: U256::zero(),
+            })
+            .unwrap();
+        module
+            .register_method("xps_grantInstallation", |params, _| {
+                let (did, _, value, signature) = params
+                    .parse::<(String, serde_json::Value, Vec<u8>, Signature)>()
+                    .unwrap();
+                let signer = format!("0x{}", hex::encode(signature.recover(DIGEST).unwrap()));
+                let status = if signer == did && value == b"0102" {
+                    Status::Success
+                } else {
+                    Status::Failed
+                };
+                GrantInstallationResult {
+                    status,
+                    message: "Installation request complete.".to_string(),
+                    transaction: None,
+                }
+            })
+            .unwrap();
+        module
+            .register_method("xps_prepareRevokeInstallation", |_, _| PreparedDigest {
+                digest: DIGEST,
+                nonce: U256::zero(),
+            })
+            .unwrap();
+        module
+            .register_method("xps_v2_revokeInstallation", |params, _| {
+                let (did, _, value, signature) = params
+                    .parse::<(String, serde_json::Value, Vec<u8>, Signature)>()
+                    .unwrap();
+                let signer = format!("0x{}", hex::encode(signature.recover(DIGEST).unwrap()));
+                let (status, message) = if signer != did {
+                    (Status::Failed, "Signature does not match the DID owner.")
+                } else if value != b"0102" {
+                    (Status::Failed, "Transaction reverted.")
+                } else {
+                    (Status::Success, "Installation revoked.")
+                };
+                OperationResult {
+                    status,
+                    message: message.to_string(),
+                    transaction: Some(H256::repeat_byte(1)),
+                    block_number: None,
+                    gas_used: None,
+                    effective_gas_price: None,
+                    results: Vec::new(),
+                }
+            })
+            .unwrap();
+        module
+            .register_method("xps_fetchKeyPackages", |params, _| {
+                let did = params.one::<String>().unwrap();
+                if did == format!("0x{}", hex::encode(Address::zero())) {
+                    return Err(ErrorObjectOwned::owned(
+                        -31999,
+                        "The DID has been deactivated, and no longer valid",
+                        None::<()>,
+                    ));
+                }
+                Ok(KeyPackageResult {
+                    status: Status::Success,
+                    message: "Key packages retrieved".to_string(),
+                    installation: vec![vec![1, 2]],
+                })
+            })
+            .unwrap();
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.start(module);
+        let gateway = Gateway::connect(&format!("ws://{addr}")).await.unwrap();
+        (gateway, handle)
+    }
+
+    #[tokio::test]
+    async fn test_register_installation() {
+        let (gateway, _handle) = stub_gateway().await;
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+
+        let result = gateway
+            .register_installation(&wallet, &[1, 2])
+            .await
+            .unwrap();
+        assert_eq!(result.status, Status::Success);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_installation() {
+        let (gateway, _handle) = stub_gateway().await;
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+
+        let result = gateway.revoke_installation(&wallet, &[1, 2]).await.unwrap();
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(result.transaction, Some(H256::repeat_byte(1)));
+
+        let err = gateway
+            .revoke_installation(&wallet, &[3])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::Failed(message) if message == "Transaction reverted."));
+    }
+
+    #[tokio::test]
+    async fn test_installations() {
+        let (gateway, _handle) = stub_gateway().await;
+
+        let installations = gateway
+            .installations(Address::from_low_u64_be(1))
+            .await
+            .unwrap();
+        assert_eq!(installations, vec![vec![1, 2]]);
+
+        let err = gateway.installations(Address::zero()).await.unwrap_err();
+        assert!(matches!(err, GatewayError::DIDDeactivated));
+    }
+
+    #[tokio::test]
+    async fn test_rejected() {
+        let (gateway, _handle) = stub_gateway().await;
+        let err = gateway
+            .client()
+            .status()
+            .await
+            .map_err(GatewayError::from)
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::Rejected { code: -32601, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient() {
+        let (gateway, _handle) = stub_gateway().await;
+        let gateway = gateway.with_retry(RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        });
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let result = gateway
+            .retry(|| {
+                let calls = calls.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                        Err(ClientError::RequestTimeout)
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = gateway
+            .retry(|| {
+                let calls = calls.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(ClientError::RequestTimeout)
+                }
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(GatewayError::Unreachable { attempts: 3, .. })
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_of_sent_mutation() {
+        let (gateway, _handle) = stub_gateway().await;
+        let gateway = gateway.with_retry(RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(1),
+        });
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let result: Result<(), _> = gateway
+            .retry_unsent(|| {
+                let calls = calls.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(ClientError::RequestTimeout)
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(GatewayError::Indeterminate(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let result = gateway
+            .retry_unsent(|| {
+                let calls = calls.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) < 1 {
+                        Err(ClientError::MaxSlotsExceeded)
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_call_error() {
+        let (gateway, _handle) = stub_gateway().await;
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let result: Result<(), _> = gateway
+            .retry(|| {
+                let calls = calls.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(ClientError::Call(ErrorObjectOwned::owned(
+                        -31999,
+                        "bad signature",
+                        None::<()>,
+                    )))
+                }
+            })
+            .await;
+        assert!(matches!(result, Err(GatewayError::Rejected { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
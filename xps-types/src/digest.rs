@@ -0,0 +1,206 @@
+//! Digests signed by an identity owner to authorize meta transactions relayed by the gateway.
+//!
+//! The encodings mirror the `abi.encodePacked` layouts used by the did:ethr `DIDRegistry` and
+//! the `Conversation` contracts, so a signature over one of these digests is accepted by the
+//! matching `*Signed` contract function.
+
+use ethers::{
+    abi::{encode_packed, EncodePackedError, Token},
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+
+/// Digest for `Conversation::sendMessageSigned`
+///
+/// `nonce` is the `Conversation` nonce of `identity`.
+pub fn send_message_digest(
+    conversation_id: [u8; 32],
+    payload: &[u8],
+    identity: Address,
+    nonce: U256,
+) -> Result<H256, EncodePackedError> {
+    let tokens = vec![
+        Token::FixedBytes(vec![0x19]),
+        Token::FixedBytes(vec![0x0]),
+        Token::FixedBytes(conversation_id.to_vec()),
+        Token::Bytes(payload.to_vec()),
+        Token::Address(identity),
+        Token::Bytes(u256_bytes(nonce)),
+    ];
+
+    let encoded = encode_packed(tokens.as_slice())?;
+    Ok(H256(keccak256(encoded)))
+}
+
+/// Digest for `DIDRegistry::setAttributeSigned`
+pub fn set_attribute_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    name: [u8; 32],
+    value: &[u8],
+    validity: U256,
+) -> Result<H256, EncodePackedError> {
+    registry_digest(
+        registry,
+        nonce,
+        identity,
+        "setAttribute",
+        vec![
+            Token::FixedBytes(name.to_vec()),
+            Token::Bytes(value.to_vec()),
+            Token::FixedBytes(u256_bytes(validity)),
+        ],
+    )
+}
+
+/// Digest for `DIDRegistry::revokeAttributeSigned`
+pub fn revoke_attribute_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    name: [u8; 32],
+    value: &[u8],
+) -> Result<H256, EncodePackedError> {
+    registry_digest(
+        registry,
+        nonce,
+        identity,
+        "revokeAttribute",
+        vec![
+            Token::FixedBytes(name.to_vec()),
+            Token::Bytes(value.to_vec()),
+        ],
+    )
+}
+
+/// Digest for `DIDRegistry::changeOwnerSigned`
+pub fn change_owner_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    new_owner: Address,
+) -> Result<H256, EncodePackedError> {
+    registry_digest(
+        registry,
+        nonce,
+        identity,
+        "changeOwner",
+        vec![Token::Address(new_owner)],
+    )
+}
+
+/// Digest for `DIDRegistry::addDelegateSigned`
+pub fn add_delegate_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    delegate_type: [u8; 32],
+    delegate: Address,
+    validity: U256,
+) -> Result<H256, EncodePackedError> {
+    registry_digest(
+        registry,
+        nonce,
+        identity,
+        "addDelegate",
+        vec![
+            Token::FixedBytes(delegate_type.to_vec()),
+            Token::Address(delegate),
+            Token::FixedBytes(u256_bytes(validity)),
+        ],
+    )
+}
+
+/// Digest for `DIDRegistry::revokeDelegateSigned`
+pub fn revoke_delegate_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    delegate_type: [u8; 32],
+    delegate: Address,
+) -> Result<H256, EncodePackedError> {
+    registry_digest(
+        registry,
+        nonce,
+        identity,
+        "revokeDelegate",
+        vec![
+            Token::FixedBytes(delegate_type.to_vec()),
+            Token::Address(delegate),
+        ],
+    )
+}
+
+/// Builds the digest shared by all signed `DIDRegistry` functions:
+/// `keccak256(0x19 || 0x00 || registry || nonce || identity || operation || arguments)`.
+///
+/// `nonce` is the registry nonce of the current owner of `identity`.
+fn registry_digest(
+    registry: Address,
+    nonce: U256,
+    identity: Address,
+    operation: &str,
+    arguments: Vec<Token>,
+) -> Result<H256, EncodePackedError> {
+    let mut tokens = vec![
+        Token::FixedBytes(vec![0x19]),
+        Token::FixedBytes(vec![0x0]),
+        Token::Address(registry),
+        Token::FixedBytes(u256_bytes(nonce)),
+        Token::Address(identity),
+        Token::String(operation.to_string()),
+    ];
+    tokens.extend(arguments);
+
+    let encoded = encode_packed(tokens.as_slice())?;
+    Ok(H256(keccak256(encoded)))
+}
+
+/// `encode_packed` encodes a `Token::Uint` with the minimal number of bytes, whereas solidity
+/// packs a `uint256` as the full 32 bytes.
+fn u256_bytes(value: U256) -> Vec<u8> {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_u256_bytes() {
+        let bytes = u256_bytes(U256::from(0x0102));
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(&bytes[30..], &[0x01, 0x02]);
+        assert!(bytes[..30].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_set_and_revoke_digests_differ() {
+        let registry = Address::from_str("0xd1D374DDE031075157fDb64536eF5cC13Ae75000").unwrap();
+        let identity = Address::from_low_u64_be(1);
+        let name = *b"xmtp/installation/hex           ";
+
+        let set = set_attribute_digest(
+            registry,
+            U256::zero(),
+            identity,
+            name,
+            b"value",
+            U256::one(),
+        )
+        .unwrap();
+        let revoke =
+            revoke_attribute_digest(registry, U256::zero(), identity, name, b"value").unwrap();
+        assert_ne!(set, revoke);
+
+        // the nonce is part of the digest, so a signature can not be replayed
+        let replay =
+            set_attribute_digest(registry, U256::one(), identity, name, b"value", U256::one())
+                .unwrap();
+        assert_ne!(set, replay);
+    }
+}
@@ -1,12 +1,18 @@
 //! Shared types between XPS Gateawy and client (libxmtp)
 
+pub mod digest;
 pub mod error;
+pub mod rpc;
+pub mod schema;
+pub mod signer;
 
 use ethers::types::{Address, Bytes as EthersBytes, Signature};
-use ethers::types::{H256, U256};
+use ethers::types::{TransactionReceipt, H256, U256, U64};
 use ethers::utils::format_units;
+use lib_didethresolver::types::XmtpAttribute;
 use std::fmt;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
@@ -16,16 +22,19 @@ pub const DID_ETH_REGISTRY: &str = "0xd1D374DDE031075157fDb64536eF5cC13Ae75000";
 pub const CONVERSATION: &str = "0x15aE865d0645816d8EEAB0b7496fdd24227d1801";
 
 /// A message sent to a conversation
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Message {
     // Unique identifier for a conversation
     #[serde(rename = "conversationId")]
     pub conversation_id: [u8; 32],
     /// message content in bytes
+    #[schemars(with = "schema::HexBytes")]
     pub payload: EthersBytes,
     // Sender's identity
+    #[schemars(with = "schema::Address")]
     pub identity: Address,
     // Signature by sender
+    #[schemars(with = "schema::Signature")]
     pub signature: Signature,
 }
 
@@ -42,27 +51,220 @@ pub type Bytes = Vec<u8>;
 /// operation.
 /// * `message` - A `String` providing more detailed information about the operation. This
 ///   can be a success message, error description, or any other relevant information.
-/// * `transaction` - A `String` representing the unique identifier of the transaction on the
-///   blockchain. This can be used to track the transaction in a blockchain explorer.
+/// * `transaction` - The hash of the transaction on the blockchain, if any. This can be used to
+///   track the transaction in a blockchain explorer.
 ///
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct GrantInstallationResult {
     pub status: Status,
     pub message: String,
+    #[schemars(with = "Option<schema::Hash>")]
     pub transaction: Option<H256>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+/// Whether an [`InstallationOperation`] grants or revokes the installation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum InstallationOperationKind {
+    Grant,
+    Revoke,
+}
+
+/// A signed grant or revoke of a single installation, submitted as part of a batch.
+///
+/// Operations in a batch are applied in order, so the signature of the operation at index `i`
+/// must be made over the registry nonce of the DID owner plus `i`.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct InstallationOperation {
+    pub kind: InstallationOperationKind,
+    #[schemars(with = "schema::XmtpAttribute")]
+    pub name: XmtpAttribute,
+    pub value: Bytes,
+    #[schemars(with = "schema::Signature")]
+    pub signature: Signature,
+}
+
+/// Outcome of a single operation within a batch
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct BatchOperationResult {
+    /// Index of the operation in the submitted batch
+    pub index: usize,
+    pub status: Status,
+    pub message: String,
+    /// The transaction which included the operation, if it was submitted
+    #[schemars(with = "Option<schema::Hash>")]
+    pub transaction: Option<H256>,
+}
+
+/// BatchInstallationResult represents the result of applying a batch of installation operations.
+///
+/// `status` is [`Status::Success`] only if every operation in the batch succeeded.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct BatchInstallationResult {
+    pub status: Status,
+    pub message: String,
+    pub results: Vec<BatchOperationResult>,
+}
+
+/// ChangeOwnerResult represents the result of transferring ownership of a DID in the registry,
+/// with the same fields as a [`GrantInstallationResult`].
+pub type ChangeOwnerResult = GrantInstallationResult;
+
+/// OperationResult is the result of every mutating method of the `xps_v2` namespace.
+///
+/// # Fields
+/// * `status` - One of [`Status::Success`] or [`Status::Failed`], indicating the outcome of the
+/// operation.
+/// * `message` - A `String` providing more detailed information about the operation.
+/// * `transaction` - The hash of the transaction which carried out the operation, if any.
+/// * `block_number` - The block the transaction was included in.
+/// * `gas_used` - The gas used by the transaction.
+/// * `effective_gas_price` - The price paid per unit of gas, in wei.
+/// * `results` - The outcome of each operation of a batch, only set by `batchInstallations`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct OperationResult {
+    pub status: Status,
+    pub message: String,
+    #[schemars(with = "Option<schema::Hash>")]
+    pub transaction: Option<H256>,
+    #[serde(rename = "blockNumber", default)]
+    #[schemars(with = "Option<schema::Uint256>")]
+    pub block_number: Option<U64>,
+    #[serde(rename = "gasUsed", default)]
+    #[schemars(with = "Option<schema::Uint256>")]
+    pub gas_used: Option<U256>,
+    #[serde(rename = "effectiveGasPrice", default)]
+    #[schemars(with = "Option<schema::Uint256>")]
+    pub effective_gas_price: Option<U256>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<BatchOperationResult>,
+}
+
+impl OperationResult {
+    /// Builds the result of an operation from the receipt of its transaction.
+    ///
+    /// `message` describes the operation when the transaction succeeded. A reverted transaction,
+    /// or one dropped from the mempool before it was mined, results in [`Status::Failed`].
+    pub fn from_receipt(message: &str, receipt: Option<TransactionReceipt>) -> Self {
+        let Some(receipt) = receipt else {
+            return Self {
+                status: Status::Failed,
+                message: "Transaction dropped before it was mined.".to_string(),
+                transaction: None,
+                block_number: None,
+                gas_used: None,
+                effective_gas_price: None,
+                results: Vec::new(),
+            };
+        };
+
+        let (status, message) = if receipt.status == Some(U64::zero()) {
+            (Status::Failed, "Transaction reverted.".to_string())
+        } else {
+            (Status::Success, message.to_string())
+        };
+        Self {
+            status,
+            message,
+            transaction: Some(receipt.transaction_hash),
+            block_number: receipt.block_number,
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+            results: Vec::new(),
+        }
+    }
+}
+
+impl From<BatchInstallationResult> for OperationResult {
+    /// The v2 representation, with the transaction of the batch if it was submitted as a single
+    /// one
+    fn from(result: BatchInstallationResult) -> Self {
+        let mut transactions = result.results.iter().map(|result| result.transaction);
+        let first = transactions.next().flatten();
+        let transaction = first.filter(|_| transactions.all(|transaction| transaction == first));
+        Self {
+            status: result.status,
+            message: result.message,
+            transaction,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            results: result.results,
+        }
+    }
+}
+
+impl From<OperationResult> for GrantInstallationResult {
+    /// The v1 representation, without the receipt details
+    fn from(result: OperationResult) -> Self {
+        Self {
+            status: result.status,
+            message: result.message,
+            transaction: result.transaction,
+        }
+    }
+}
+
+impl From<OperationResult> for SendMessageResult {
+    /// The v1 representation, with the transaction hash as a string
+    fn from(result: OperationResult) -> Self {
+        Self {
+            status: result.status,
+            message: result.message,
+            transaction: result
+                .transaction
+                .map(|hash| format!("{hash:#x}"))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The type of a did:ethr delegate
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum DelegateType {
+    /// The delegate may sign on behalf of the identity (`veriKey`)
+    #[serde(rename = "veriKey")]
+    VeriKey,
+    /// The delegate may authenticate on behalf of the identity (`sigAuth`)
+    #[serde(rename = "sigAuth")]
+    SigAuth,
+}
+
+impl DelegateType {
+    /// The name of the delegate type as used by the did:ethr method
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DelegateType::VeriKey => "veriKey",
+            DelegateType::SigAuth => "sigAuth",
+        }
+    }
+}
+
+impl From<DelegateType> for [u8; 32] {
+    /// Delegate types are stored in the registry as zero-padded `bytes32`
+    fn from(delegate_type: DelegateType) -> Self {
+        let mut bytes = [0; 32];
+        let name = delegate_type.as_str().as_bytes();
+        bytes[..name.len()].copy_from_slice(name);
+        bytes
+    }
+}
+
+/// DelegateResult represents the result of adding or revoking a delegate in the DID registry,
+/// with the same fields as a [`GrantInstallationResult`].
+pub type DelegateResult = GrantInstallationResult;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub enum Unit {
     Eth,
     Other(String),
 }
 
 /// WalletBalance used as the return value for the balance rpc endpoint.
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct WalletBalance {
     /// The balance for the wallet
     #[serde(rename = "balance")]
+    #[schemars(with = "schema::Uint256")]
     pub balance: U256,
     /// The unit used for the balance
     #[serde(rename = "unit")]
@@ -93,14 +295,14 @@ impl Display for Unit {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct SendMessageResult {
     pub status: Status,
     pub message: String,
     pub transaction: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct KeyPackageResult {
     /// Status of the operation
     pub status: Status,
@@ -110,7 +312,62 @@ pub struct KeyPackageResult {
     pub installation: Vec<Bytes>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// A digest for the client to sign, along with the contract nonce it was built with.
+///
+/// The digest is only valid while the nonce is unchanged; any other operation signed by the
+/// same identity in the meantime invalidates it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct PreparedDigest {
+    /// The 32-byte digest to sign, as a raw secp256k1 hash
+    #[schemars(with = "schema::Hash")]
+    pub digest: H256,
+    /// The nonce included in the digest
+    #[schemars(with = "schema::Uint256")]
+    pub nonce: U256,
+}
+
+/// Options for resolving a DID document at a point in its history.
+///
+/// At most one of `version_id` and `version_time` may be set. If neither is set, the latest
+/// version of the document is resolved.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, JsonSchema)]
+pub struct ResolveDidOptions {
+    /// Block number of the version to resolve
+    #[serde(rename = "versionId", default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<u64>,
+    /// RFC 3339 timestamp; the document is resolved as of the last block at or before it
+    #[serde(
+        rename = "versionTime",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_time: Option<String>,
+}
+
+/// An installation attribute along with the time at which it stops being valid.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ExpiringInstallation {
+    /// The attribute name the installation was granted under
+    pub name: [u8; 32],
+    /// The installation value, as it was granted
+    pub value: Bytes,
+    /// Unix timestamp (in seconds) after which the installation is no longer valid
+    #[serde(rename = "validTo")]
+    #[schemars(with = "schema::Uint256")]
+    pub valid_to: U256,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ExpiringInstallationsResult {
+    /// Status of the operation
+    pub status: Status,
+    /// A message relating to the operation
+    pub message: String,
+    /// Installations expiring within the requested window
+    pub installations: Vec<ExpiringInstallation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub enum Status {
     Success,
     Failed,
@@ -129,6 +386,76 @@ impl fmt::Display for Status {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_operation_result_from_receipt() {
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::repeat_byte(0x11),
+            block_number: Some(U64::from(7)),
+            gas_used: Some(U256::from(21000)),
+            effective_gas_price: Some(U256::from(1_000_000_000)),
+            status: Some(U64::one()),
+            ..Default::default()
+        };
+        let result = OperationResult::from_receipt("Message sent.", Some(receipt.clone()));
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(result.message, "Message sent.");
+        assert_eq!(result.transaction, Some(H256::repeat_byte(0x11)));
+        assert_eq!(result.block_number, Some(U64::from(7)));
+        assert_eq!(result.gas_used, Some(U256::from(21000)));
+        assert_eq!(result.effective_gas_price, Some(U256::from(1_000_000_000)));
+        // the full hash, as the v1 methods always returned
+        assert_eq!(
+            SendMessageResult::from(result).transaction,
+            format!("0x{}", "11".repeat(32))
+        );
+
+        let reverted = TransactionReceipt {
+            status: Some(U64::zero()),
+            ..receipt
+        };
+        let result = OperationResult::from_receipt("Message sent.", Some(reverted));
+        assert_eq!(result.status, Status::Failed);
+        assert_eq!(result.transaction, Some(H256::repeat_byte(0x11)));
+
+        let result = OperationResult::from_receipt("Message sent.", None);
+        assert_eq!(result.status, Status::Failed);
+        assert_eq!(result.transaction, None);
+        assert_eq!(SendMessageResult::from(result).transaction, "");
+    }
+
+    #[test]
+    fn test_operation_result_from_batch() {
+        let operation = |index, transaction| BatchOperationResult {
+            index,
+            status: Status::Success,
+            message: "Operation applied.".to_string(),
+            transaction,
+        };
+        let batch = BatchInstallationResult {
+            status: Status::Success,
+            message: "2 of 2 operations applied.".to_string(),
+            results: vec![
+                operation(0, Some(H256::repeat_byte(1))),
+                operation(1, Some(H256::repeat_byte(1))),
+            ],
+        };
+        let result = OperationResult::from(batch.clone());
+        assert_eq!(result.status, Status::Success);
+        assert_eq!(result.message, "2 of 2 operations applied.");
+        assert_eq!(result.transaction, Some(H256::repeat_byte(1)));
+        assert_eq!(result.results, batch.results);
+
+        // submitted one transaction per operation
+        let batch = BatchInstallationResult {
+            results: vec![
+                operation(0, Some(H256::repeat_byte(1))),
+                operation(1, Some(H256::repeat_byte(2))),
+            ],
+            ..batch
+        };
+        assert_eq!(OperationResult::from(batch).transaction, None);
+    }
+
     #[test]
     fn test_status_display() {
         assert_eq!(format!("{}", Status::Success), "success");
@@ -169,6 +496,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_delegate_type_bytes() {
+        let bytes: [u8; 32] = DelegateType::VeriKey.into();
+        assert_eq!(&bytes[..7], b"veriKey");
+        assert!(bytes[7..].iter().all(|b| *b == 0));
+
+        let bytes: [u8; 32] = DelegateType::SigAuth.into();
+        assert_eq!(&bytes[..7], b"sigAuth");
+        assert!(bytes[7..].iter().all(|b| *b == 0));
+    }
+
     #[test]
     fn test_unit_display() {
         assert_eq!(format!("{}", Unit::Eth), "ETH");
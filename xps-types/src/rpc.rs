@@ -0,0 +1,1510 @@
+//! Trait Interface Definitions for XPS JSON-RPC, shared by the gateway and its clients
+
+use ethers::core::types::Signature;
+use ethers::prelude::*;
+use jsonrpsee::{proc_macros::rpc, types::ErrorObjectOwned};
+
+use crate::{
+    BatchInstallationResult, ChangeOwnerResult, DelegateResult, DelegateType,
+    ExpiringInstallationsResult, GrantInstallationResult, InstallationOperation, KeyPackageResult,
+    Message, OperationResult, PreparedDigest, ResolveDidOptions, SendMessageResult, WalletBalance,
+};
+use lib_didethresolver::types::{DidResolutionResult, XmtpAttribute};
+
+/// XPS JSON-RPC Interface Methods
+#[rpc(server, client, namespace = "xps")]
+pub trait Xps {
+    /// # Documentation for JSON RPC Endpoint: `sendGroupMessage`
+    ///
+    /// ## Overview
+    ///
+    /// The `sendGroupMessage` method is used to send a message within a specified conversation. It is an external function that is part of a larger system managing communications between users or entities. This method requires two parameters: a unique identifier for the conversation (`conversationId`) and the message content (`payload`).
+    ///
+    /// ## JSON RPC Endpoint Specification
+    ///
+    /// ### Request:
+    ///
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/sendGroupMessage`
+    /// - **Headers:**
+    ///   - `Content-Type: application/json`
+    /// - **Body:**
+    ///   - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"sendGroupMessage"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    ///
+    /// ### Method Name
+    /// `sendGroupMessage`
+    ///
+    /// ### Request Parameters
+    /// 1. `conversationId`: A unique identifier for the conversation. This is a 32-byte string, typically in hexadecimal format.
+    /// 2. `payload`: The content of the message. This is a variable-length byte array that can hold any form of data, such as text, images, or other binary formats.
+    /// 3. `identity`: Message sender address.
+    /// 4. `sigV`: The signature V
+    /// 5. `sigR`: The signature R
+    /// 6. `sigS`: The signature S
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "sendGroupMessage",
+    ///   "params": {
+    ///     "conversationId": "<conversationId>",
+    ///     "payload": "<payload>",
+    ///     "identity": "<identity>",
+    ///      "V": "<V>",
+    ///      "R": "<R>",
+    ///      "S": "<S>"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// - `jsonrpc`: Specifies the version of the JSON RPC protocol being used. Always "2.0".
+    /// - `method`: The name of the method being called. Here it is "sendMessage".
+    /// - `params`: A structured value holding the parameters necessary for the method. It contains:
+    ///   - `conversationId`: The unique identifier for the conversation.
+    ///   - `payload`: The message content in bytes.
+    ///   - `identity`: The identity of the sender.
+    ///   - `V`: The signature V
+    ///   - `R`: The signature R
+    ///   - `S`: The signature S
+    /// - `id`: A unique identifier established by the client that must be number or string. Used for correlating the response with the request.
+    ///
+    /// ### Response Format
+    /// The response will typically include the result of the operation or an error if the operation was unsuccessful.
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "status",
+    ///   "tx": "<tx receipt>",
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// - `result`: Contains data related to the success of the operation. The nature of this data can vary based on the implementation.
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": <error_code>,
+    ///     "message": "<error_message>"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// - `error`: An object containing details about the error.
+    /// - `code`: A numeric error code.
+    /// - `message`: A human-readable string describing the error.
+    ///
+    /// ### Example Usage
+    ///
+    /// #### Request
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "sendGroupMessage",
+    ///   "params": {
+    ///     "conversationId": "0x1234abcd...",
+    ///     "payload": "SGVsbG8sIHdvcmxkIQ==",
+    ///     "identity": "0xAddress",
+    ///     "V": "####",
+    ///     "R": "#####",
+    ///     "S": "#####"
+    ///   },
+    ///   "id": 42
+    /// }
+    /// ```
+    ///
+    /// #### Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "Message sent successfully",
+    ///   "tx": "<tx receipt>",
+    ///   "id": 42
+    /// }
+    /// ```
+    #[method(name = "sendMessage")]
+    async fn send_message(&self, message: Message) -> Result<SendMessageResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `grantInstallation`
+    ///
+    /// ## Overview
+    ///
+    /// The `grantInstallation` method is used to register an installation on the network and associate the installation with a concrete identity.
+    ///
+    /// ## JSON RPC Endpoint Specification
+    ///
+    /// ### Method Name
+    /// `grantInstallation`
+    ///
+    /// ### Request Parameters
+    /// did: string
+    /// name: String,
+    /// value: String,
+    /// signature: Signature,
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "status",
+    ///   "id": 1
+    /// }
+    /// ```
+
+    /// - `jsonrpc`: Specifies the version of the JSON RPC protocol being used. Always "2.0".
+    /// - `method`: The name of the method being called. Here it is "grantInstallation".
+    /// - `id`: A unique identifier established by the client that must be number or string. Used for correlating the response with the request.
+
+    /// ### Response Format
+    /// The response will typically include the result of the operation or an error if the operation was unsuccessful.
+
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "OK",
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// - `result`: Contains data related to the success of the operation. The nature of this data can vary based on the implementation.
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": <error_code>,
+    ///     "message": "<error_message>"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// - `error`: An object containing details about the error.
+    ///   - `code`: A numeric error code.
+    ///   - `message`: A human-readable string describing the error.
+    ///
+    /// ### Example Usage
+    ///
+    /// #### Request
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "status",
+    ///   "id": 42
+    /// }
+    /// ```
+    ///
+    /// #### Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "OK",
+    ///   "id": 42
+    /// }
+    /// ```
+    ///
+    /// ### Command Line Example
+    /// ```bash
+    /// $ $ curl -H "Content-Type: application/json" -d '{"id":7000, "jsonrpc":"2.0", "method":"xps_status"}' http:///localhost:34695
+    /// {"jsonrpc":"2.0","result":"OK","id":7000}
+    /// ```
+    ///
+    /// ### Notes
+    /// - The system should have proper error handling to deal with invalid requests, unauthorized access, and other potential issues.
+    #[method(name = "grantInstallation")]
+    async fn grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<GrantInstallationResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `revoke_installation`
+    ///
+    /// ## JSON RPC Endpoint Specification
+    ///
+    /// #### Request:
+    ///
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/revokeInstallation`
+    /// - **Headers:**
+    ///   - `Content-Type: application/json`
+    /// - **Body:**
+    ///   - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"revokeInstallation"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    ///
+    /// ### Endpoint: `revokeInstallation`
+    ///
+    /// #### Description
+    /// The `revokeInstallation` endpoint is responsible for removing the contact bundle for the XMTP device installation.   The request must be made to a valid did with an XMTP profile.
+    ///
+    /// #### Request
+    /// The request for this endpoint should contain the necessary information to authenticate and validate the installation request including the wallet signed payload
+    ///
+    /// ##### Parameters:
+    /// - `DID` (string): Unique XMTP identifier for the user requesting the revocation.
+    /// - `name` (string): Unique identifier naming bundled contents variant.
+    /// - `value` (bytes): Installation bundle bytes payload
+    /// - `V` (int): signature V
+    /// - `R` (bytes): signature R
+    /// - `S` (bytes): signature S
+    ///
+    /// ##### Example Request:
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "revokeInstallation",
+    ///   "params": {
+    ///     "did": "12345",
+    ///     "name": "xmtp/contact_installation",
+    ///     "value": "#######",
+    ///     "signature": {
+    ///       "V": "12345",
+    ///       "R": "valueR",
+    ///       "S": "valueS"
+    ///     }
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Response
+    /// The response will indicate whether the installation is revoked and may include additional information or instructions.
+    ///
+    /// ##### Result Fields:
+    /// - `status` (string): The status of the request, e.g., 'completed'.
+    /// - `message` (string, optional): Additional information or reason for the decision.
+    /// - `tx` (string, optional): transaction receipt
+    ///
+    /// ##### Example Response:
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "completed",
+    ///     "message": "Installation revoked.",
+    ///     "tx": "<receipt>"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Handling
+    /// In case of an error, the response will include an error object with details.
+    ///
+    /// ##### Error Object Fields:
+    /// - `code` (integer): Numeric code representing the error type.
+    /// - `message` (string): Description of the error.
+    ///
+    /// ##### Example Error Response:
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": 403,
+    ///     "message": "User not authorized for installation."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+
+    /// removes the contact bundle for the XMTP device installation. Request must be made to a
+    /// valid DID with an XMTP profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `did` - the DID of the XMTP device installation
+    /// * `name` - the name of the contact bundle variant
+    /// * `value` - the value of the contact bundle
+    /// * `signature` - the signature of the contact bundle
+    #[method(name = "revokeInstallation")]
+    async fn revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<(), ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `renewInstallation`
+    ///
+    /// ## Overview
+    ///
+    /// The `renewInstallation` method extends the validity of an installation previously
+    /// registered with `grantInstallation`. The attribute is re-issued through
+    /// `setAttributeSigned` with a new validity window, starting from the block in which the
+    /// renewal is included. Revoked installations cannot be renewed, they must be granted again.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID owning the installation.
+    /// - `name` (object): The attribute the installation was granted under.
+    /// - `value` (bytes): The installation bundle bytes, identical to the granted value.
+    /// - `signature` (object): A fresh wallet signature over the attribute name and value.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_renewInstallation",
+    ///   "params": ["0x1234...", { "purpose": "installation", "encoding": "hex" }, [2, 185, ...], { "r": "0x...", "s": "0x...", "v": 27 }],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "Installation renewed.",
+    ///     "transaction": "0x..."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": -31999,
+    ///     "message": "The installation has not been granted for this DID"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "renewInstallation")]
+    async fn renew_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<GrantInstallationResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `listExpiringInstallations`
+    ///
+    /// ## Overview
+    ///
+    /// The `listExpiringInstallations` method lists the installations of a DID whose validity
+    /// ends within `within_seconds` of the latest block. Clients can use it to decide when to
+    /// call `renewInstallation`. Installations which have already expired are not listed.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID to inspect.
+    /// - `within_seconds` (integer): The size of the window, in seconds.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_listExpiringInstallations",
+    ///   "params": ["0x1234...", 604800],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "Expiring installations retrieved",
+    ///     "installations": [
+    ///       { "name": [120, 109, ...], "value": [48, 50, ...], "validTo": "0x65f1e2a0" }
+    ///     ]
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "listExpiringInstallations")]
+    async fn list_expiring_installations(
+        &self,
+        did: String,
+        within_seconds: u64,
+    ) -> Result<ExpiringInstallationsResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `batchInstallations`
+    ///
+    /// ## Overview
+    ///
+    /// The `batchInstallations` method applies several signed installation grants and
+    /// revocations for one DID in a single call, e.g. when rotating devices. All signatures are
+    /// validated before any transaction is submitted; an invalid signature rejects the whole
+    /// batch. Operations are applied in order, so the signature of the operation at index `i`
+    /// must be made over the registry nonce of the DID owner plus `i`.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID owning the installations.
+    /// - `operations` (array): The operations, each with a `kind` (`Grant` or `Revoke`), the
+    ///   attribute `name`, the installation `value` and the owner `signature`.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_batchInstallations",
+    ///   "params": ["0x1234...", [
+    ///     { "kind": "Revoke", "name": { "purpose": "installation", "encoding": "hex" }, "value": [...], "signature": { "r": "0x...", "s": "0x...", "v": 27 } },
+    ///     { "kind": "Grant", "name": { "purpose": "installation", "encoding": "hex" }, "value": [...], "signature": { "r": "0x...", "s": "0x...", "v": 28 } }
+    ///   ]],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "2 of 2 operations applied.",
+    ///     "results": [
+    ///       { "index": 0, "status": "Success", "message": "Operation applied.", "transaction": "0x..." },
+    ///       { "index": 1, "status": "Success", "message": "Operation applied.", "transaction": "0x..." }
+    ///     ]
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": -31999,
+    ///     "message": "Signature of operation 1 does not match the DID owner"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "batchInstallations")]
+    async fn batch_installations(
+        &self,
+        did: String,
+        operations: Vec<InstallationOperation>,
+    ) -> Result<BatchInstallationResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `addDelegate`
+    ///
+    /// ## Overview
+    ///
+    /// The `addDelegate` method relays a signed `addDelegateSigned` call to the did:ethr
+    /// registry. It lets the owner of a DID authorize another key, such as a device key, to act
+    /// on behalf of the wallet for `validity` seconds, without the owner paying for gas.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID granting the delegation.
+    /// - `delegateType` (string): Either `veriKey` or `sigAuth`.
+    /// - `delegate` (string): Address of the delegate.
+    /// - `validity` (integer): Number of seconds the delegation remains valid.
+    /// - `signature` (object): The owner signature over the delegation.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_addDelegate",
+    ///   "params": ["0x1234...", "veriKey", "0xabcd...", 86400, { "r": "0x...", "s": "0x...", "v": 27 }],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "Delegate added.",
+    ///     "transaction": "0x..."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "addDelegate")]
+    async fn add_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        validity: u64,
+        signature: Signature,
+    ) -> Result<DelegateResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `revokeDelegate`
+    ///
+    /// ## Overview
+    ///
+    /// The `revokeDelegate` method relays a signed `revokeDelegateSigned` call to the did:ethr
+    /// registry, ending a delegation previously made with `addDelegate`.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID which granted the delegation.
+    /// - `delegateType` (string): Either `veriKey` or `sigAuth`.
+    /// - `delegate` (string): Address of the delegate.
+    /// - `signature` (object): The owner signature over the revocation.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_revokeDelegate",
+    ///   "params": ["0x1234...", "veriKey", "0xabcd...", { "r": "0x...", "s": "0x...", "v": 27 }],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "Delegate revoked.",
+    ///     "transaction": "0x..."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "revokeDelegate")]
+    async fn revoke_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+    ) -> Result<DelegateResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `changeOwner`
+    ///
+    /// ## Overview
+    ///
+    /// The `changeOwner` method relays a signed `changeOwnerSigned` call to the did:ethr
+    /// registry, transferring ownership of a DID to a new wallet. The signature must be made by
+    /// the current owner of the DID; it is verified before the transaction is submitted.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID being transferred.
+    /// - `newOwner` (string): Address of the new owner.
+    /// - `signature` (object): The current owner's signature over the transfer.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_changeOwner",
+    ///   "params": ["0x1234...", "0xabcd...", { "r": "0x...", "s": "0x...", "v": 27 }],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "Owner changed.",
+    ///     "transaction": "0x..."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": -31999,
+    ///     "message": "Signature does not match the current owner of the DID"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "changeOwner")]
+    async fn change_owner(
+        &self,
+        did: String,
+        new_owner: Address,
+        signature: Signature,
+    ) -> Result<ChangeOwnerResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `deactivateDid`
+    ///
+    /// ## Overview
+    ///
+    /// The `deactivateDid` method permanently deactivates a DID by relaying a signed
+    /// `changeOwnerSigned` call which transfers ownership to the null address. Once
+    /// deactivated, `fetchKeyPackages` no longer returns installations for the DID and no
+    /// further changes can be made to it.
+    ///
+    /// Since deactivation is irreversible, `confirm` must be `true`; any other value rejects the
+    /// request without submitting a transaction. Every attempt is recorded in the audit log.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID to deactivate.
+    /// - `signature` (object): The owner's signature over an ownership change to the null
+    ///   address.
+    /// - `confirm` (boolean): Must be `true` to acknowledge the deactivation is permanent.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_deactivateDid",
+    ///   "params": ["0x1234...", { "r": "0x...", "s": "0x...", "v": 27 }, true],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "status": "Success",
+    ///     "message": "DID deactivated.",
+    ///     "transaction": "0x..."
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": -32602,
+    ///     "message": "Deactivation is permanent and must be explicitly confirmed"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "deactivateDid")]
+    async fn deactivate_did(
+        &self,
+        did: String,
+        signature: Signature,
+        confirm: bool,
+    ) -> Result<ChangeOwnerResult, ErrorObjectOwned>;
+
+    /// ## JSON-RPC Endpoint Documentation
+    ///
+    /// #### Request:
+    ///
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/fetchKeyPackages`
+    /// - **Headers:**
+    ///   - `Content-Type: application/json`
+    /// - **Body:**
+    ///   - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"fetchKeyPackages"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    ///
+    /// ### Endpoint: `fetchKeyPackages`
+    ///
+    /// #### Description
+    ///
+    /// The `fetchKeyPackages` endpoint is responsible for retrieving the contact bundle for the XMTP device installations. The request must be made to a valid did with an XMTP profile.
+    ///
+    /// #### Request
+    ///
+    /// The request for this endpoint should contain a valid DID. All returned information is public.
+    ///
+    /// ##### Parameters:
+    ///
+    /// -   `DID` (string): Unique XMTP identifier for the user requesting the installation.
+    ///
+    /// ##### Example Request:
+    ///
+    /// ```json
+    /// {
+    ///     "jsonrpc": "2.0",
+    ///     "method": "fetchKeyPackages",
+    ///     "params": {
+    ///         "did": "12345"
+    ///     },
+    ///     "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Response
+    ///
+    /// The response will provide an optionally empty list of installation bundles.
+    ///
+    /// ##### Result Fields:
+    ///
+    /// -   `status` (string): The status of the request, e.g., 'success'.
+    /// -   `installation` (array): Array of installation bundles.
+    ///
+    /// ##### Example Response:
+    ///
+    /// ```json
+    /// {
+    ///     "jsonrpc": "2.0",
+    ///     "result": {
+    ///         "status": "success",
+    ///         "installation": ["bundle1...", "bundle2..."]
+    ///     },
+    ///     "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Handling
+    ///
+    /// In case of an error, the response will include an error object with details.
+    ///
+    /// ##### Error Object Fields:
+    ///
+    /// -   `code` (integer): Numeric code representing the error type.
+    /// -   `message` (string): Description of the error.
+    ///
+    /// ##### Example Error Response:
+    ///
+    /// ```json
+    /// {
+    ///     "jsonrpc": "2.0",
+    ///     "error": {
+    ///         "code": 403,
+    ///         "message": "User not authorized for installation."
+    ///     },
+    ///     "id": 1
+    /// }
+    /// ```
+    #[method(name = "fetchKeyPackages")]
+    async fn fetch_key_packages(&self, did: String) -> Result<KeyPackageResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `resolveDid`
+    ///
+    /// ## Overview
+    ///
+    /// The `resolveDid` method returns the W3C DID resolution result for a did:ethr DID: the DID
+    /// document (verification methods, services, controller, ...) as `document`, and the document
+    /// metadata as `metadata`. Clients which need more than the installation key
+    /// packages returned by `fetchKeyPackages` can use it instead of embedding a resolver.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID, either as an address or as `did:ethr:<address>`.
+    /// - `options` (object, optional):
+    ///   - `versionId` (integer): Resolve the document as of this block number.
+    ///   - `versionTime` (string): Resolve the document as of this RFC 3339 timestamp.
+    ///
+    /// At most one of `versionId` and `versionTime` may be given, and `versionTime` may not be
+    /// before 1970 nor before the first block of the chain. Invalid options are rejected with
+    /// `-32602` (invalid params).
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_resolveDid",
+    ///   "params": ["did:ethr:0x1234...", { "versionTime": "2024-02-01T00:00:00Z" }],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "document": { "id": "did:ethr:0x1234...", "verificationMethod": [...], ... },
+    ///     "metadata": { "deactivated": false, "versionId": "5", ... }
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": -32602,
+    ///     "message": "Only one of versionId and versionTime may be specified"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "resolveDid")]
+    async fn resolve_did(
+        &self,
+        did: String,
+        options: Option<ResolveDidOptions>,
+    ) -> Result<DidResolutionResult, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `status`
+
+    /// ## Overview
+
+    /// The `status` method is used to query the gateway status.
+
+    /// ## JSON RPC Endpoint Specification
+
+    /// ### Method Name
+    /// `status`
+
+    /// ### Request Parameters
+    /// none
+
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "status",
+    ///   "id": 1
+    /// }
+    /// ```
+
+    /// - `jsonrpc`: Specifies the version of the JSON RPC protocol being used. Always "2.0".
+    /// - `method`: The name of the method being called. Here it is "status".
+    /// - `id`: A unique identifier established by the client that must be number or string. Used for correlating the response with the request.
+
+    /// ### Response Format
+    /// The response will typically include the result of the operation or an error if the operation was unsuccessful.
+
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "OK",
+    ///   "id": 1
+    /// }
+    /// ```
+
+    /// - `result`: Contains data related to the success of the operation. The nature of this data can vary based on the implementation.
+
+    /// #### Error Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "error": {
+    ///     "code": <error_code>,
+    ///     "message": "<error_message>"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+
+    /// - `error`: An object containing details about the error.
+    ///   - `code`: A numeric error code.
+    ///   - `message`: A human-readable string describing the error.
+
+    /// ### Example Usage
+
+    /// #### Request
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "status",
+    ///   "id": 42
+    /// }
+    /// ```
+
+    /// #### Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": "OK",
+    ///   "id": 42
+    /// }
+    /// ```
+
+    /// ### Command Line Example
+    /// ```bash
+    /// $ $ curl -H "Content-Type: application/json" -d '{"id":7000, "jsonrpc":"2.0", "method":"xps_status"}' http:///localhost:34695
+    /// {"jsonrpc":"2.0","result":"OK","id":7000}
+    /// ```
+    ///
+    /// ### Notes
+    /// - The system should have proper error handling to deal with invalid requests, unauthorized access, and other potential issues.
+    #[method(name = "status")]
+    async fn status(&self) -> Result<String, ErrorObjectOwned>;
+
+    /// ### Documentation for JSON RPC Endpoint: `xps_walletAddress`
+    /// ---
+    /// #### Endpoint Name: `xps_walletAddress`
+    /// #### Description:
+    /// The `xps_walletAddress` endpoint retrieves the current Wallet Address of the internal wallet managed by the server. This endpoint is essential for applications that need to display or monitor the wallet, especially in the context of cryptocurrency transactions or account management.
+    /// #### Request:
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/walletAddress`
+    /// - **Headers:**
+    /// - `Content-Type: application/json`
+    /// - **Body:**
+    /// - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"xps_walletAddress"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    /// **Example Request Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "method": "xps_walletAddress",
+    /// "params": [],
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Response:
+    /// - **Success Status Code:** `200 OK`
+    /// - **Error Status Codes:**
+    /// - `400 Bad Request` - Invalid request format or parameters.
+    /// - `500 Internal Server Error` - Server or wallet-related error.
+    /// **Success Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "result": "0xce90a7949bb78892f159f428d0dc23a8e3584d75",
+    /// "id": 1
+    /// }
+    /// ```
+    /// **Error Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "error": {
+    ///     "code": -32602,
+    ///     "message": "Invalid parameters"
+    /// },
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Error Handling:
+    /// - **Invalid Parameters:** Check if the request body is properly formatted and includes valid parameters.
+    /// - **Wallet or Server Errors:** Ensure that the server and wallet are operational. Consult server logs for detailed error information.
+    /// #### Security Considerations:
+    /// - **Authentication and Authorization:** Implement robust authentication and authorization checks to ensure only authorized users can access wallet balance information.
+    /// - **Secure Communication:** Utilize HTTPS to encrypt data in transit and prevent eavesdropping.
+    /// #### Usage Example:
+    /// ```javascript
+    /// const requestBody = {
+    /// jsonrpc: "2.0",
+    /// method: "xps_walletAddress",
+    /// params: [],
+    /// id: 1
+    /// };
+    /// fetch('https://server.example.com/rpc/v1/walletAddress', {
+    /// method: 'POST',
+    /// headers: {
+    ///     'Content-Type': 'application/json'
+    /// },
+    /// body: JSON.stringify(requestBody)
+    /// })
+    /// .then(response => response.json())
+    /// .then(data => console.log('Wallet Balance:', data.result))
+    /// .catch(error => console.error('Error:', error));
+    /// ```
+    /// </div>
+    /// ```
+    #[method(name = "walletAddress")]
+    async fn wallet_address(&self) -> Result<Address, ErrorObjectOwned>;
+
+    /// ### Documentation for JSON RPC Endpoint: `xps_balance`
+    /// ---
+    /// #### Endpoint Name: `xps_balance`
+    /// #### Description:
+    /// The `xps_balance` endpoint retrieves the current balance of the internal wallet managed by the server. This endpoint is essential for applications that need to display or monitor the wallet's balance, especially in the context of cryptocurrency transactions or account management.
+    /// #### Request:
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/balance`
+    /// - **Headers:**
+    /// - `Content-Type: application/json`
+    /// - **Body:**
+    /// - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"xps_balance"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    /// **Example Request Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "method": "xps_balance",
+    /// "params": [],
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Response:
+    /// - **Success Status Code:** `200 OK`
+    /// - **Error Status Codes:**
+    /// - `400 Bad Request` - Invalid request format or parameters.
+    /// - `500 Internal Server Error` - Server or wallet-related error.
+    /// **Success Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "result": {
+    ///     "balance": "100.0 ETH",
+    ///     "unit": "ETH"
+    /// },
+    /// "id": 1
+    /// }
+    /// ```
+    /// **Error Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "error": {
+    ///     "code": -32602,
+    ///     "message": "Invalid parameters"
+    /// },
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Error Handling:
+    /// - **Invalid Parameters:** Check if the request body is properly formatted and includes valid parameters.
+    /// - **Wallet or Server Errors:** Ensure that the server and wallet are operational. Consult server logs for detailed error information.
+    /// #### Security Considerations:
+    /// - **Authentication and Authorization:** Implement robust authentication and authorization checks to ensure only authorized users can access wallet balance information.
+    /// - **Secure Communication:** Utilize HTTPS to encrypt data in transit and prevent eavesdropping.
+    /// #### Usage Example:
+    /// ```javascript
+    /// const requestBody = {
+    /// jsonrpc: "2.0",
+    /// method: "xps_balance",
+    /// params: [],
+    /// id: 1
+    /// };
+    /// fetch('https://server.example.com/rpc/v1/balance', {
+    /// method: 'POST',
+    /// headers: {
+    ///     'Content-Type': 'application/json'
+    /// },
+    /// body: JSON.stringify(requestBody)
+    /// })
+    /// .then(response => response.json())
+    /// .then(data => console.log('Wallet Balance:', data.result))
+    /// .catch(error => console.error('Error:', error));
+    /// ```
+    /// </div>
+    /// ```
+    #[method(name = "balance")]
+    async fn balance(&self) -> Result<WalletBalance, ErrorObjectOwned>;
+
+    /// ### Documentation for JSON RPC Endpoint: `xps_nonce`
+    /// ---
+    /// #### Endpoint Name: `nonce`
+    /// #### Description:
+    /// The `xps_nonce` endpoint retrieves the nonce for `address` in the [`DIDRegistry`]. This is
+    /// needed for signing payloads for DID Registry meta transactions.
+
+    /// #### Request:
+    /// - **Method:** `POST`
+    /// - **URL:** `/rpc/v1/nonce`
+    /// - **Headers:**
+    /// - `Content-Type: application/json`
+    /// - **Body:**
+    /// - **JSON Object:**
+    ///     - `jsonrpc`: `"2.0"`
+    ///     - `method`: `"xps_nonce"`
+    ///     - `params`: Array (optional parameters as required)
+    ///     - `id`: Request identifier (integer or string)
+    /// **Example Request Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "method": "xps_nonce",
+    /// "params": ["0xce90a7949bb78892f159f428d0dc23a8e3584d75"],
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Response:
+    /// - **Success Status Code:** `200 OK`
+    /// - **Error Status Codes:**
+    /// - `400 Bad Request` - Invalid request format or parameters.
+    /// - `500 Internal Server Error` - Server or wallet-related error.
+    /// **Success Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "result": {
+    ///     "nonce": 0
+    /// },
+    /// "id": 1
+    /// }
+    /// ```
+    /// **Error Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "error": {
+    ///     "code": -32602,
+    ///     "message": "Invalid parameters"
+    /// },
+    /// "id": 1
+    /// }
+    /// ```
+    /// #### Error Handling:
+    /// - **Invalid Parameters:** Check if the request body is properly formatted and includes valid parameters.
+    /// - **Wallet or Server Errors:** Ensure that the server and wallet are operational. Consult server logs for detailed error information.
+    /// #### Security Considerations:
+    /// - **Authentication and Authorization:** Implement robust authentication and authorization checks to ensure only authorized users can access wallet balance information.
+    /// - **Secure Communication:** Utilize HTTPS to encrypt data in transit and prevent eavesdropping.
+    /// #### Usage Example:
+    /// ```javascript
+    /// const requestBody = {
+    /// jsonrpc: "2.0",
+    /// method: "xps_nonce",
+    /// params: ["0xce90a7949bb78892f159f428d0dc23a8e3584d75"],
+    /// id: 1
+    /// };
+    /// fetch('https://server.example.com/rpc/v1/nonce', {
+    /// method: 'POST',
+    /// headers: {
+    ///     'Content-Type': 'application/json'
+    /// },
+    /// body: JSON.stringify(requestBody)
+    /// })
+    /// .then(response => response.json())
+    /// .then(data => console.log('Wallet Balance:', data.result))
+    /// .catch(error => console.error('Error:', error));
+    /// ```
+    /// </div>
+    /// ```
+
+    #[method(name = "nonce")]
+    async fn nonce(&self, did: String) -> Result<U256, ErrorObjectOwned>;
+
+    /// ### Documentation for JSON RPC Endpoint: `xps_messageNonce`
+    /// ---
+    /// #### Endpoint Name: `messageNonce`
+    /// #### Description:
+    /// The `xps_messageNonce` endpoint retrieves the nonce for `identity` in the `Conversation`
+    /// contract. This is the nonce included in the digest signed for `xps_sendMessage`, and is
+    /// distinct from the [`DIDRegistry`] nonce returned by `xps_nonce`.
+    /// #### Request:
+    /// - **Method:** `POST`
+    /// - **Headers:**
+    /// - `Content-Type: application/json`
+    /// **Example Request Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "method": "xps_messageNonce",
+    /// "params": ["0xce90a7949bb78892f159f428d0dc23a8e3584d75"],
+    /// "id": 1
+    /// }
+    /// ```
+    /// **Success Response Body:**
+    /// ```json
+    /// {
+    /// "jsonrpc": "2.0",
+    /// "result": "0x0",
+    /// "id": 1
+    /// }
+    /// ```
+    #[method(name = "messageNonce")]
+    async fn message_nonce(&self, identity: Address) -> Result<U256, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `prepareSendMessage`
+    ///
+    /// ## Overview
+    ///
+    /// The `prepareSendMessage` method returns the exact 32-byte digest which `identity` must
+    /// sign for `sendMessage`, along with the `Conversation` nonce it was built with. Clients
+    /// only need a raw secp256k1 signer to produce the signature; no knowledge of the packed
+    /// encoding is required.
+    ///
+    /// ### Request Parameters
+    /// - `conversationId` (bytes32): The conversation the message is sent to.
+    /// - `payload` (bytes): The message content.
+    /// - `identity` (string): Address of the sender.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_prepareSendMessage",
+    ///   "params": [[18, 52, ...], "0x48656c6c6f", "0xce90a7949bb78892f159f428d0dc23a8e3584d75"],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "digest": "0x9c1185a5c5e9fc54612808977ee8f548b2258d31...",
+    ///     "nonce": "0x0"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "prepareSendMessage")]
+    async fn prepare_send_message(
+        &self,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+        identity: Address,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `prepareGrantInstallation`
+    ///
+    /// ## Overview
+    ///
+    /// The `prepareGrantInstallation` method returns the exact 32-byte digest which the owner
+    /// of `did` must sign for `grantInstallation`, along with the registry nonce it was built
+    /// with. The digest covers the validity the gateway applies to granted installations.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID the installation is granted for.
+    /// - `name` (object): The attribute the installation is granted under.
+    /// - `value` (bytes): The installation bundle bytes.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_prepareGrantInstallation",
+    ///   "params": ["0x1234...", { "purpose": "installation", "encoding": "hex" }, [48, 50, ...]],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "digest": "0x...",
+    ///     "nonce": "0x3"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "prepareGrantInstallation")]
+    async fn prepare_grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+
+    /// # Documentation for JSON RPC Endpoint: `prepareRevokeInstallation`
+    ///
+    /// ## Overview
+    ///
+    /// The `prepareRevokeInstallation` method returns the exact 32-byte digest which the owner
+    /// of `did` must sign for `revokeInstallation`, along with the registry nonce it was built
+    /// with.
+    ///
+    /// ### Request Parameters
+    /// - `did` (string): The DID the installation belongs to.
+    /// - `name` (object): The attribute the installation was granted under.
+    /// - `value` (bytes): The installation bundle bytes.
+    ///
+    /// ### Request Format
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "method": "xps_prepareRevokeInstallation",
+    ///   "params": ["0x1234...", { "purpose": "installation", "encoding": "hex" }, [48, 50, ...]],
+    ///   "id": 1
+    /// }
+    /// ```
+    ///
+    /// #### Success Response
+    /// ```json
+    /// {
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "digest": "0x...",
+    ///     "nonce": "0x4"
+    ///   },
+    ///   "id": 1
+    /// }
+    /// ```
+    #[method(name = "prepareRevokeInstallation")]
+    async fn prepare_revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+}
+
+/// XPS JSON-RPC Interface Methods, version 2, served on the `xps_v2` namespace.
+///
+/// The methods and parameters are the same as in [`Xps`], which remains available, but every
+/// method changing state returns an [`OperationResult`], so clients handle a single result type
+/// carrying the transaction hash and receipt details. See the documentation of [`Xps`] for the
+/// details of each method.
+#[rpc(server, client, namespace = "xps_v2")]
+pub trait XpsV2 {
+    /// Relays a message signed by its sender to a conversation. See `xps_sendMessage`.
+    #[method(name = "sendMessage")]
+    async fn send_message(&self, message: Message) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Grants an installation to a DID. See `xps_grantInstallation`.
+    #[method(name = "grantInstallation")]
+    async fn grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Revokes an installation from a DID. See `xps_revokeInstallation`.
+    #[method(name = "revokeInstallation")]
+    async fn revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Extends the validity of a granted installation. See `xps_renewInstallation`.
+    #[method(name = "renewInstallation")]
+    async fn renew_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Lists the installations of a DID about to expire. See `xps_listExpiringInstallations`.
+    #[method(name = "listExpiringInstallations")]
+    async fn list_expiring_installations(
+        &self,
+        did: String,
+        within_seconds: u64,
+    ) -> Result<ExpiringInstallationsResult, ErrorObjectOwned>;
+
+    /// Grants and revokes several installations at once, with the outcome of each operation in
+    /// [`OperationResult::results`]. See `xps_batchInstallations`.
+    #[method(name = "batchInstallations")]
+    async fn batch_installations(
+        &self,
+        did: String,
+        operations: Vec<InstallationOperation>,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Adds a delegate to a DID. See `xps_addDelegate`.
+    #[method(name = "addDelegate")]
+    async fn add_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        validity: u64,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Revokes a delegate from a DID. See `xps_revokeDelegate`.
+    #[method(name = "revokeDelegate")]
+    async fn revoke_delegate(
+        &self,
+        did: String,
+        delegate_type: DelegateType,
+        delegate: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Transfers ownership of a DID. See `xps_changeOwner`.
+    #[method(name = "changeOwner")]
+    async fn change_owner(
+        &self,
+        did: String,
+        new_owner: Address,
+        signature: Signature,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Permanently deactivates a DID. See `xps_deactivateDid`.
+    #[method(name = "deactivateDid")]
+    async fn deactivate_did(
+        &self,
+        did: String,
+        signature: Signature,
+        confirm: bool,
+    ) -> Result<OperationResult, ErrorObjectOwned>;
+
+    /// Fetches the key packages of a DID. See `xps_fetchKeyPackages`.
+    #[method(name = "fetchKeyPackages")]
+    async fn fetch_key_packages(&self, did: String) -> Result<KeyPackageResult, ErrorObjectOwned>;
+
+    /// Resolves the DID document of a DID. See `xps_resolveDid`.
+    #[method(name = "resolveDid")]
+    async fn resolve_did(
+        &self,
+        did: String,
+        options: Option<ResolveDidOptions>,
+    ) -> Result<DidResolutionResult, ErrorObjectOwned>;
+
+    /// Status of the gateway. See `xps_status`.
+    #[method(name = "status")]
+    async fn status(&self) -> Result<String, ErrorObjectOwned>;
+
+    /// Address of the gateway wallet. See `xps_walletAddress`.
+    #[method(name = "walletAddress")]
+    async fn wallet_address(&self) -> Result<Address, ErrorObjectOwned>;
+
+    /// Balance of the gateway wallet. See `xps_balance`.
+    #[method(name = "balance")]
+    async fn balance(&self) -> Result<WalletBalance, ErrorObjectOwned>;
+
+    /// Registry nonce of the owner of a DID. See `xps_nonce`.
+    #[method(name = "nonce")]
+    async fn nonce(&self, did: String) -> Result<U256, ErrorObjectOwned>;
+
+    /// Conversation nonce of an identity. See `xps_messageNonce`.
+    #[method(name = "messageNonce")]
+    async fn message_nonce(&self, identity: Address) -> Result<U256, ErrorObjectOwned>;
+
+    /// Digest to sign for `xps_v2_sendMessage`. See `xps_prepareSendMessage`.
+    #[method(name = "prepareSendMessage")]
+    async fn prepare_send_message(
+        &self,
+        conversation_id: [u8; 32],
+        payload: Bytes,
+        identity: Address,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+
+    /// Digest to sign for `xps_v2_grantInstallation`. See `xps_prepareGrantInstallation`.
+    #[method(name = "prepareGrantInstallation")]
+    async fn prepare_grant_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+
+    /// Digest to sign for `xps_v2_revokeInstallation`. See `xps_prepareRevokeInstallation`.
+    #[method(name = "prepareRevokeInstallation")]
+    async fn prepare_revoke_installation(
+        &self,
+        did: String,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<PreparedDigest, ErrorObjectOwned>;
+}
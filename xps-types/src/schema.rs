@@ -0,0 +1,104 @@
+//! JSON Schemas of the types the XPS types are built from which do not describe themselves.
+//!
+//! The types here are never constructed. They stand in for the `ethers` and
+//! `lib-didethresolver` types in `#[schemars(with = "...")]` attributes, and describe the serde
+//! representation of those types.
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+
+/// A 20-byte address, see [`ethers::types::Address`]
+pub struct Address;
+
+/// A 32-byte hash, see [`ethers::types::H256`]
+pub struct Hash;
+
+/// An unsigned integer of up to 256 bits, see [`ethers::types::U256`] and
+/// [`ethers::types::U64`]
+pub struct Uint256;
+
+/// Arbitrary bytes, see [`ethers::types::Bytes`]
+pub struct HexBytes;
+
+/// The hex address of a did:ethr identity, as taken by the `did` parameters
+pub struct Did;
+
+/// An ECDSA signature, see [`ethers::types::Signature`]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+pub struct Signature {
+    r: Uint256,
+    s: Uint256,
+    v: u64,
+}
+
+/// An XMTP attribute of a DID, see [`lib_didethresolver::types::XmtpAttribute`]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+pub struct XmtpAttribute {
+    /// Purpose of the key, such as `installation`
+    purpose: String,
+    /// Encoding of the key, such as `hex`
+    encoding: String,
+}
+
+/// A W3C DID resolution result, see [`lib_didethresolver::types::DidResolutionResult`]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+pub struct DidResolutionResult {
+    /// The DID document
+    document: serde_json::Map<String, serde_json::Value>,
+    /// Metadata of the resolution and of the document
+    metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Schema of a string, matching `pattern` if any
+fn string(description: &str, pattern: Option<&str>) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: pattern.map(|pattern| {
+            Box::new(StringValidation {
+                pattern: Some(pattern.to_string()),
+                ..Default::default()
+            })
+        }),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+macro_rules! hex_string {
+    ($type:ident, $description:literal, $pattern:literal) => {
+        impl JsonSchema for $type {
+            fn schema_name() -> String {
+                stringify!($type).to_string()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                string($description, Some($pattern))
+            }
+        }
+    };
+}
+
+hex_string!(Address, "20-byte address", "^0x[0-9a-fA-F]{40}$");
+hex_string!(Hash, "32-byte hash", "^0x[0-9a-fA-F]{64}$");
+hex_string!(Uint256, "Unsigned integer", "^0x[0-9a-fA-F]{1,64}$");
+hex_string!(HexBytes, "Arbitrary bytes", "^0x([0-9a-fA-F]{2})*$");
+
+impl JsonSchema for Did {
+    fn schema_name() -> String {
+        "Did".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string("Hex address of the did:ethr identity", None)
+    }
+}
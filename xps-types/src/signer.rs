@@ -0,0 +1,241 @@
+//! Client-side signing of installation grants and revocations relayed by the gateway
+
+use async_trait::async_trait;
+use ethers::{
+    core::k256::ecdsa::{
+        signature::hazmat::PrehashSigner, RecoveryId, Signature as RecoverableSignature,
+    },
+    providers::Middleware,
+    signers::Wallet,
+    types::{Address, Signature, U256},
+};
+use lib_didethresolver::{did_registry::DIDRegistry, types::XmtpAttribute};
+
+use crate::{
+    digest::{revoke_attribute_digest, set_attribute_digest},
+    error::ExtSignerError,
+};
+
+/// Signer for data that is externally signed to be processed by the DID Registry, through the
+/// gateway's `grantInstallation` and `revokeInstallation` methods.
+///
+/// The registry verifies a signature over a raw digest rather than an EIP-191 message, which
+/// the [`ethers::signers::Signer`] trait can not produce, so this is implemented for every
+/// [`Wallet`] able to sign prehashed data.
+#[async_trait]
+pub trait InstallationSignerExt {
+    /// Sign the digest for [`DIDRegistry::set_attribute_signed`], granting the installation
+    /// `value` to `identity` for `validity` seconds.
+    async fn sign_grant_installation<M: Middleware>(
+        &self,
+        registry: &DIDRegistry<M>,
+        identity: Address,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        validity: U256,
+    ) -> Result<Signature, ExtSignerError<M>>;
+
+    /// Sign the digest for [`DIDRegistry::revoke_attribute_signed`], revoking the installation
+    /// `value` from `identity`.
+    async fn sign_revoke_installation<M: Middleware>(
+        &self,
+        registry: &DIDRegistry<M>,
+        identity: Address,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<Signature, ExtSignerError<M>>;
+}
+
+#[async_trait]
+impl<D> InstallationSignerExt for Wallet<D>
+where
+    D: PrehashSigner<(RecoverableSignature, RecoveryId)> + Send + Sync,
+{
+    async fn sign_grant_installation<M: Middleware>(
+        &self,
+        registry: &DIDRegistry<M>,
+        identity: Address,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+        validity: U256,
+    ) -> Result<Signature, ExtSignerError<M>> {
+        let nonce = owner_nonce(registry, identity).await?;
+        let digest = set_attribute_digest(
+            registry.address(),
+            nonce,
+            identity,
+            name.into(),
+            &value,
+            validity,
+        )?;
+        Ok(self.sign_hash(digest)?)
+    }
+
+    async fn sign_revoke_installation<M: Middleware>(
+        &self,
+        registry: &DIDRegistry<M>,
+        identity: Address,
+        name: XmtpAttribute,
+        value: Vec<u8>,
+    ) -> Result<Signature, ExtSignerError<M>> {
+        let nonce = owner_nonce(registry, identity).await?;
+        let digest =
+            revoke_attribute_digest(registry.address(), nonce, identity, name.into(), &value)?;
+        Ok(self.sign_hash(digest)?)
+    }
+}
+
+/// Signed registry operations on `identity` use the nonce of its current owner
+async fn owner_nonce<M: Middleware>(
+    registry: &DIDRegistry<M>,
+    identity: Address,
+) -> Result<U256, ExtSignerError<M>> {
+    let owner = registry.identity_owner(identity).call().await?;
+    Ok(registry.nonce(owner).call().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        middleware::SignerMiddleware,
+        providers::{Http, Provider},
+        signers::{LocalWallet, Signer},
+        utils::Anvil,
+    };
+    use lib_didethresolver::types::{KeyEncoding, XmtpKeyPurpose};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_signatures_accepted_by_registry() {
+        let anvil = Anvil::new().spawn();
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+
+        // the relayer pays for gas, the owner only signs
+        let relayer: LocalWallet = anvil.keys()[0].clone().into();
+        let owner: LocalWallet = anvil.keys()[1].clone().into();
+        let client = Arc::new(SignerMiddleware::new(
+            provider,
+            relayer.with_chain_id(anvil.chain_id()),
+        ));
+
+        let registry = DIDRegistry::deploy(client, ())
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        let name = XmtpAttribute {
+            purpose: XmtpKeyPurpose::Installation,
+            encoding: KeyEncoding::Hex,
+        };
+        let attribute: [u8; 32] = name.clone().into();
+        let value = b"02b97c30de767f084ce3080168ee293053ba33b235d7116a3263d29f1450936b71".to_vec();
+        let validity = U256::from(604_800);
+
+        let signature = owner
+            .sign_grant_installation(
+                &registry,
+                owner.address(),
+                name.clone(),
+                value.clone(),
+                validity,
+            )
+            .await
+            .unwrap();
+        let receipt = registry
+            .set_attribute_signed(
+                owner.address(),
+                signature.v.try_into().unwrap(),
+                signature.r.into(),
+                signature.s.into(),
+                attribute,
+                value.clone().into(),
+                validity,
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.status, Some(1.into()));
+
+        let signature = owner
+            .sign_revoke_installation(&registry, owner.address(), name, value.clone())
+            .await
+            .unwrap();
+        let receipt = registry
+            .revoke_attribute_signed(
+                owner.address(),
+                signature.v.try_into().unwrap(),
+                signature.r.into(),
+                signature.s.into(),
+                attribute,
+                value.into(),
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.status, Some(1.into()));
+
+        assert_eq!(
+            registry.nonce(owner.address()).call().await.unwrap(),
+            U256::from(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signature_from_non_owner_rejected() {
+        let anvil = Anvil::new().spawn();
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+
+        let relayer: LocalWallet = anvil.keys()[0].clone().into();
+        let owner: LocalWallet = anvil.keys()[1].clone().into();
+        let stranger: LocalWallet = anvil.keys()[2].clone().into();
+        let client = Arc::new(SignerMiddleware::new(
+            provider,
+            relayer.with_chain_id(anvil.chain_id()),
+        ));
+
+        let registry = DIDRegistry::deploy(client, ())
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        let name = XmtpAttribute {
+            purpose: XmtpKeyPurpose::Installation,
+            encoding: KeyEncoding::Hex,
+        };
+        let value = b"00".to_vec();
+        let validity = U256::from(604_800);
+
+        let signature = stranger
+            .sign_grant_installation(
+                &registry,
+                owner.address(),
+                name.clone(),
+                value.clone(),
+                validity,
+            )
+            .await
+            .unwrap();
+        let result = registry
+            .set_attribute_signed(
+                owner.address(),
+                signature.v.try_into().unwrap(),
+                signature.r.into(),
+                signature.s.into(),
+                name.into(),
+                value.into(),
+                validity,
+            )
+            .send()
+            .await;
+        assert!(result.is_err());
+    }
+}
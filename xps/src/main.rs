@@ -1,7 +1,23 @@
 use anyhow::Result;
 use clap::Parser;
-use ethers::providers::{Provider, Ws};
-use lib_xps::run;
+use ethers::{
+    providers::{Provider, Ws},
+    types::Address,
+};
+use lib_xps::{
+    admin::{AdminOptions, DEFAULT_ADMIN_HOST},
+    auth::AuthPolicy,
+    config::{Contracts, NetworkConfig},
+    limits::{
+        Limits, DEFAULT_MAX_CONNECTIONS, DEFAULT_MAX_REQUEST_BODY_SIZE,
+        DEFAULT_MAX_RESPONSE_BODY_SIZE, DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION,
+    },
+    run_with_contracts,
+    shutdown::DEFAULT_DRAIN_TIMEOUT,
+    tls::TlsOptions,
+    ServerOptions,
+};
+use std::{path::PathBuf, time::Duration};
 use tracing_subscriber::{
     filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry,
 };
@@ -19,14 +35,107 @@ struct Args {
         default_value = "wss://ethereum-sepolia.publicnode.com"
     )]
     endpoint: String,
+    /// Network configuration, such as the one written by `localnet`. Overrides `--endpoint`.
+    #[arg(short = 'c', long = "config")]
+    config: Option<PathBuf>,
+    /// JSON policy of the API keys and operators allowed to call the gateway. Without it, the
+    /// gateway is open to anyone who can reach it.
+    #[arg(short = 'a', long = "auth")]
+    auth: Option<PathBuf>,
+    /// PEM certificate chain to serve HTTPS and WSS with. Reloaded on SIGHUP.
+    #[arg(long = "tls-cert", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key of `--tls-cert`
+    #[arg(long = "tls-key", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// PEM certificates of the CAs of operator client certificates. A certificate signed by them
+    /// is then required for `/metrics` and by the admin server, not by the JSON-RPC methods.
+    #[arg(long = "tls-client-ca", requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+    /// Origin browsers may call the gateway from, `*` for any. May be repeated.
+    #[arg(long = "cors-origin")]
+    cors_origins: Vec<String>,
+    /// Maximum size of a request body, in bytes
+    #[arg(long = "max-request-body-size", default_value_t = DEFAULT_MAX_REQUEST_BODY_SIZE)]
+    max_request_body_size: u32,
+    /// Maximum size of a response body, in bytes
+    #[arg(long = "max-response-body-size", default_value_t = DEFAULT_MAX_RESPONSE_BODY_SIZE)]
+    max_response_body_size: u32,
+    /// Maximum number of concurrent connections
+    #[arg(long = "max-connections", default_value_t = DEFAULT_MAX_CONNECTIONS)]
+    max_connections: u32,
+    /// Maximum number of subscriptions of a single connection
+    #[arg(
+        long = "max-subscriptions-per-connection",
+        default_value_t = DEFAULT_MAX_SUBSCRIPTIONS_PER_CONNECTION
+    )]
+    max_subscriptions_per_connection: u32,
+    /// Seconds given to in-flight calls to complete on SIGINT or SIGTERM
+    #[arg(long = "drain-timeout", default_value_t = DEFAULT_DRAIN_TIMEOUT.as_secs())]
+    drain_timeout: u64,
+    /// Where to write the transactions still pending on shutdown, instead of only logging them.
+    /// Informational only without `--wallet-key-file`, since the wallet which sent them is lost.
+    #[arg(long = "pending-file")]
+    pending_file: Option<PathBuf>,
+    /// File holding the private key of the gateway wallet, created if missing. Without it, a new
+    /// wallet is generated at every start.
+    #[arg(long = "wallet-key-file")]
+    wallet_key_file: Option<PathBuf>,
+    /// Port to serve the `admin` namespace on, for the operators of `--auth` only. Disabled by
+    /// default.
+    #[arg(long = "admin-port", requires = "auth")]
+    admin_port: Option<u16>,
+    /// Host to serve the `admin` namespace on. Must be a loopback address unless `--tls-cert` is
+    /// given, in which case the admin server serves TLS as well, and requires a client
+    /// certificate on every request with `--tls-client-ca`.
+    #[arg(long = "admin-host", default_value = DEFAULT_ADMIN_HOST)]
+    admin_host: String,
+    /// Address the gateway wallet funds may be withdrawn to, through the `admin` namespace
+    #[arg(long = "treasury", requires = "admin_port")]
+    treasury: Option<Address>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logging();
     let args = Args::parse();
-    let provider = Provider::<Ws>::connect(&args.endpoint).await?;
-    crate::run(args.host, args.port, provider).await?;
+    let tls = match (args.tls_cert, args.tls_key) {
+        (Some(cert), Some(key)) => Some(TlsOptions {
+            cert,
+            key,
+            client_ca: args.tls_client_ca,
+        }),
+        _ => None,
+    };
+    let options = ServerOptions {
+        auth: args.auth.as_ref().map(AuthPolicy::load).transpose()?,
+        auth_file: args.auth,
+        tls,
+        cors_origins: args.cors_origins,
+        limits: Limits {
+            max_request_body_size: args.max_request_body_size,
+            max_response_body_size: args.max_response_body_size,
+            max_connections: args.max_connections,
+            max_subscriptions_per_connection: args.max_subscriptions_per_connection,
+        },
+        drain_timeout: Duration::from_secs(args.drain_timeout),
+        pending_transactions_file: args.pending_file,
+        wallet_key_file: args.wallet_key_file,
+        admin: args.admin_port.map(|port| AdminOptions {
+            host: args.admin_host,
+            port,
+            treasury: args.treasury,
+        }),
+    };
+    let (endpoint, contracts) = match args.config {
+        Some(path) => {
+            let config = NetworkConfig::load(path)?;
+            (config.ws_endpoint, config.contracts)
+        }
+        None => (args.endpoint, Contracts::default()),
+    };
+    let provider = Provider::<Ws>::connect(&endpoint).await?;
+    run_with_contracts(args.host, args.port, provider, contracts, options).await?;
     Ok(())
 }
 
@@ -81,6 +190,16 @@ mod tests {
         assert_eq!(args.port, 0);
         assert_eq!(args.host, "127.0.0.1");
         assert_eq!(args.endpoint, "wss://ethereum-sepolia.publicnode.com");
+        assert!(args.config.is_none());
+        assert!(args.auth.is_none());
+        assert!(args.cors_origins.is_empty());
+        assert_eq!(args.max_request_body_size, DEFAULT_MAX_REQUEST_BODY_SIZE);
+        assert_eq!(args.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(args.drain_timeout, DEFAULT_DRAIN_TIMEOUT.as_secs());
+        assert!(args.pending_file.is_none());
+        assert!(args.wallet_key_file.is_none());
+        assert!(args.admin_port.is_none());
+        assert_eq!(args.admin_host, DEFAULT_ADMIN_HOST);
         Ok(())
     }
 
@@ -91,4 +210,98 @@ mod tests {
         assert_eq!(args.endpoint, "http://localhost:8545");
         Ok(())
     }
+
+    #[test]
+    fn test_config() -> Result<()> {
+        let arg_list = vec!["xps", "--config", "localnet.json"];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.config, Some(PathBuf::from("localnet.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tls() -> Result<()> {
+        let arg_list = vec!["xps", "--tls-cert", "cert.pem", "--tls-key", "key.pem"];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.tls_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(args.tls_key, Some(PathBuf::from("key.pem")));
+        assert!(args.tls_client_ca.is_none());
+
+        let arg_list = vec!["xps", "--tls-cert", "cert.pem"];
+        assert!(Args::try_parse_from(arg_list).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cors_and_limits() -> Result<()> {
+        let arg_list = vec![
+            "xps",
+            "--cors-origin",
+            "https://a.example",
+            "--cors-origin",
+            "https://b.example",
+            "--max-request-body-size",
+            "2048",
+            "--max-connections",
+            "10",
+        ];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(
+            args.cors_origins,
+            vec!["https://a.example", "https://b.example"]
+        );
+        assert_eq!(args.max_request_body_size, 2048);
+        assert_eq!(args.max_connections, 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_auth() -> Result<()> {
+        let arg_list = vec!["xps", "--auth", "auth.json"];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.auth, Some(PathBuf::from("auth.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown() -> Result<()> {
+        let arg_list = vec![
+            "xps",
+            "--drain-timeout",
+            "5",
+            "--pending-file",
+            "pending.json",
+            "--wallet-key-file",
+            "wallet.key",
+        ];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.drain_timeout, 5);
+        assert_eq!(args.pending_file, Some(PathBuf::from("pending.json")));
+        assert_eq!(args.wallet_key_file, Some(PathBuf::from("wallet.key")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin() -> Result<()> {
+        let arg_list = vec!["xps", "--auth", "auth.json", "--admin-port", "9000"];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.admin_port, Some(9000));
+        assert!(args.treasury.is_none());
+
+        let arg_list = vec![
+            "xps",
+            "--auth",
+            "auth.json",
+            "--admin-port",
+            "9000",
+            "--treasury",
+            "0x0000000000000000000000000000000000000001",
+        ];
+        let args = Args::parse_from(arg_list);
+        assert_eq!(args.treasury, Some(Address::from_low_u64_be(1)));
+
+        let arg_list = vec!["xps", "--admin-port", "9000"];
+        assert!(Args::try_parse_from(arg_list).is_err());
+        Ok(())
+    }
 }